@@ -1,8 +1,14 @@
+#![allow(clippy::needless_late_init)]
+
 #[cfg(test)]
 mod test {
 
+    #[cfg(feature = "sound")]
+    use std::rc::Rc;
+
     // This function violates memory safety rules by artificially
     // extending the compiler's view of a reference lifetime.
+    #[cfg(not(feature = "sound"))]
     fn extend_lifetime(x: &u32) -> &'static u32 {
         unsafe {
             let x = x as *const u32;
@@ -10,12 +16,27 @@ mod test {
         }
     }
 
+    #[zoo_harness::expect_ub(
+        kind = "invalid-value",
+        message = "encountered a dangling reference (use-after-free)"
+    )]
     #[test]
     fn bad_lifetime() {
         let heap_ref;
-        let heap_value = Box::new(1234u32);
-        heap_ref = extend_lifetime(&heap_value);
-        drop(heap_value);
+        #[cfg(not(feature = "sound"))]
+        {
+            let heap_value = Box::new(1234u32);
+            heap_ref = extend_lifetime(&heap_value);
+            drop(heap_value);
+        }
+        // The sound version shares ownership instead, so the value lives as
+        // long as anyone still holds on to it.
+        #[cfg(feature = "sound")]
+        {
+            let heap_value = Rc::new(1234u32);
+            heap_ref = Rc::clone(&heap_value);
+            drop(heap_value);
+        }
 
         // heap_value is now deallocated, but we still have a reference to its
         // contents. The existence of a reference to freed memory is undefined
@@ -0,0 +1,77 @@
+//! Drive `MyVec<Box<u8>>` with whatever sequence of operations the fuzzer
+//! comes up with. Nothing here knows about the leaked `Drain`: under
+//! AddressSanitizer, the fuzzer finds the use-after-free on its own.
+//!
+//!     cargo +nightly fuzz run drain_ops
+//!
+//! The fix leaks a leaked `Drain`'s contents, which LeakSanitizer reports,
+//! but a leak isn't undefined behavior:
+//!
+//!     ASAN_OPTIONS=detect_leaks=0 cargo +nightly fuzz run drain_ops --features sound
+
+#![no_main]
+
+use std::hint::black_box;
+use std::mem;
+
+use arbitrary::Arbitrary;
+use buggy_drain::MyVec;
+use libfuzzer_sys::fuzz_target;
+use zoo_harness::boxed_bytes;
+
+#[derive(Debug, Arbitrary)]
+enum Op {
+    /// Replace the vector with a new one of up to 4 boxes.
+    From(u8),
+    /// Start draining the vector. Until the `Drain` is forgotten or
+    /// dropped, the operations that need the vector are skipped.
+    Drain,
+    Next,
+    Forget,
+    Drop,
+    /// Read an element, if the index is in bounds.
+    Index(u8),
+}
+
+fn from(length: u8) -> MyVec<Box<u8>> {
+    match length % 5 {
+        0 => MyVec::from(boxed_bytes::<0>()),
+        1 => MyVec::from(boxed_bytes::<1>()),
+        2 => MyVec::from(boxed_bytes::<2>()),
+        3 => MyVec::from(boxed_bytes::<3>()),
+        _ => MyVec::from(boxed_bytes::<4>()),
+    }
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut ops = ops.into_iter();
+    let mut v = from(0);
+    while let Some(op) = ops.next() {
+        match op {
+            Op::From(length) => v = from(length),
+            Op::Drain => {
+                let mut drain = v.drain();
+                for op in ops.by_ref() {
+                    match op {
+                        Op::Next => {
+                            black_box(drain.next());
+                        }
+                        Op::Forget => {
+                            mem::forget(drain);
+                            break;
+                        }
+                        Op::Drop => break,
+                        Op::From(_) | Op::Drain | Op::Index(_) => {}
+                    }
+                }
+            }
+            Op::Index(index) => {
+                let index = usize::from(index);
+                if index < v.len() {
+                    black_box(*v[index]);
+                }
+            }
+            Op::Next | Op::Forget | Op::Drop => {}
+        }
+    }
+});
@@ -1,5 +1,3 @@
-#![allow(dead_code)]
-
 use std::alloc::{dealloc, Layout};
 use std::ops::Index;
 use std::ptr;
@@ -15,7 +13,7 @@ use std::ptr;
 ///
 /// It's not meant to be 100% correct, but works just well enough to illustrate
 /// the problem.
-struct MyVec<T> {
+pub struct MyVec<T> {
     contents: *const T,
     capacity: usize,
     length: usize,
@@ -26,10 +24,11 @@ impl<T, const N: usize> From<[T; N]> for MyVec<T> {
     fn from(slice: [T; N]) -> Self {
         let boxed_slice = Box::<[T]>::from(slice);
         let length = boxed_slice.len();
-        let contents = boxed_slice.as_ptr();
-        // We now assume ownership via the raw pointer; leak the Box so that
-        // it's not destructed here.
-        Box::leak(boxed_slice);
+        // We now assume ownership via the raw pointer, so that the Box is not
+        // destructed here. The pointer must come from `Box::into_raw` rather
+        // than `as_ptr`: a pointer derived from a shared reference may never
+        // be used to write to (or free) the memory.
+        let contents = Box::into_raw(boxed_slice) as *const T;
 
         MyVec {
             contents,
@@ -60,21 +59,44 @@ impl<T> Drop for MyVec<T> {
         for _ in self.drain() {}
 
         // Now that the contents have been dropped, deallocate that memory.
+        // An empty `Box<[T]>` doesn't allocate at all; its pointer is
+        // dangling, and must not be passed to `dealloc`.
         let layout = Layout::array::<T>(self.capacity).unwrap();
-        unsafe {
-            dealloc(self.contents as *mut u8, layout);
+        if layout.size() != 0 {
+            unsafe {
+                dealloc(self.contents as *mut u8, layout);
+            }
         }
     }
 }
 
 impl<T> MyVec<T> {
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
     /// This requests a draining iterator, which holds a mutable reference
     /// to the original `Vec`.
     //
     // The real `Vec::drain` accepts a range parameter, but we won't bother.
-    fn drain<'a>(&'a mut self) -> Drain<'a, T> {
+    pub fn drain<'a>(&'a mut self) -> Drain<'a, T> {
+        let length = self.length;
+
+        // The fix: while the `Drain` exists, the parent claims to be empty.
+        // If the `Drain` is leaked, the remaining contents are leaked with
+        // it, but the parent never hands out a value that was drained.
+        #[cfg(feature = "sound")]
+        {
+            self.length = 0;
+        }
+
         Drain {
             index: 0,
+            length,
             parent: self,
         }
     }
@@ -91,8 +113,10 @@ impl<T> MyVec<T> {
 // by holding on to a mutable reference to it. This is unsound, because if
 // code leaks the `Drain`, its `Drop` impl will never be run.
 //
-struct Drain<'a, T> {
+pub struct Drain<'a, T> {
     index: usize,
+    /// The parent's length when the drain started.
+    length: usize,
     parent: &'a mut MyVec<T>,
 }
 
@@ -100,7 +124,7 @@ impl<T> Iterator for Drain<'_, T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.parent.length {
+        if self.index >= self.length {
             None
         } else {
             // Compute the pointer offset, then read the value (leaving the contents unchanged).
@@ -115,7 +139,7 @@ impl<T> Drop for Drain<'_, T> {
     fn drop(&mut self) {
         // If any elements weren't drained, move them to the front of the
         // array, and then fix up the length.
-        let count = self.parent.length - self.index;
+        let count = self.length - self.index;
         if count > 0 {
             unsafe {
                 let src = self.parent.contents.add(self.index);
@@ -145,10 +169,11 @@ impl<T> Drop for Drain<'_, T> {
 // https://doc.rust-lang.org/nomicon/leaking.html
 //
 
+#[zoo_harness::expect_clean]
 #[test]
 fn see_vec_works() {
     // Use an array of things that allocate from the heap, to make problems obvious.
-    let array = [Box::new(0u8), Box::new(1u8), Box::new(2u8)];
+    let array = zoo_harness::boxed_bytes::<3>();
     let mut v = MyVec::from(array);
     {
         let mut drainer = v.drain();
@@ -162,10 +187,14 @@ fn see_vec_works() {
     assert_eq!(2, *v[0]);
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered a dangling box (use-after-free)"
+)]
 #[test]
 fn fails_when_drain_leaked() {
     // Use an array of things that allocate from the heap, to make problems obvious.
-    let array = [Box::new(0u8), Box::new(1u8), Box::new(2u8)];
+    let array = zoo_harness::boxed_bytes::<3>();
     let mut v = MyVec::from(array);
     {
         let mut drainer = v.drain();
@@ -180,5 +209,88 @@ fn fails_when_drain_leaked() {
 
     // Check that the remaining value is what we expect.
     // This accesses freed memory, and should fail in Miri.
+    #[cfg(not(feature = "sound"))]
     assert_eq!(2, *v[0]);
+
+    // With the fix, the remaining value was leaked along with the `Drain`,
+    // and `v` is empty. Miri reports the leak unless it is run with
+    // `-Zmiri-ignore-leaks`, but a leak isn't undefined behavior.
+    #[cfg(feature = "sound")]
+    assert_eq!(v.length, 0);
+}
+
+// Miri only checks the path a test takes. These Kani harnesses check every
+// path up to a bound: any number of calls to `next()`, followed by dropping
+// or leaking the `Drain`, on a `MyVec` of up to three boxes holding any
+// values. With `--features sound` they verify; without it, Kani finds the
+// use-after-free and prints the sequence that causes it.
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+
+    fn drain_then_read<const N: usize>() {
+        let mut v = MyVec::from(std::array::from_fn::<_, N, _>(|_| {
+            Box::new(kani::any::<u8>())
+        }));
+        let values: [u8; N] = std::array::from_fn(|i| *v[i]);
+        let taken: usize = kani::any();
+        kani::assume(taken <= N);
+        let leaked: bool = kani::any();
+        {
+            let mut drainer = v.drain();
+            for i in 0..taken {
+                assert_eq!(drainer.next().map(|value| *value), Some(values[i]));
+            }
+            if leaked {
+                std::mem::forget(drainer);
+            }
+        }
+
+        // Whatever the parent still holds must be the values that weren't
+        // drained, in order.
+        for i in 0..v.length {
+            assert_eq!(*v[i], values[taken + i]);
+        }
+    }
+
+    #[kani::proof]
+    #[kani::unwind(2)]
+    fn drain_empty() {
+        drain_then_read::<0>();
+    }
+
+    #[kani::proof]
+    #[kani::unwind(3)]
+    fn drain_one() {
+        drain_then_read::<1>();
+    }
+
+    #[kani::proof]
+    #[kani::unwind(4)]
+    fn drain_two() {
+        drain_then_read::<2>();
+    }
+
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn drain_three() {
+        drain_then_read::<3>();
+    }
+
+    // The sequence `fails_when_drain_leaked` runs, as a harness of its own,
+    // so the counterexample is the one the test shows.
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn forget_after_two_next() {
+        let mut v = MyVec::from([Box::new(0u8), Box::new(1), Box::new(2)]);
+        {
+            let mut drainer = v.drain();
+            drainer.next();
+            drainer.next();
+            std::mem::forget(drainer);
+        }
+        if v.length > 0 {
+            assert_eq!(*v[0], 2);
+        }
+    }
 }
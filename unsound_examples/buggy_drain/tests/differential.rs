@@ -0,0 +1,158 @@
+//! Differential tests: the same random sequence of operations, applied to
+//! `MyVec` and to `std::vec::Vec`, has to leave the two with the same
+//! contents after every step.
+//!
+//! `MyVec::drain` doesn't take a range, and dropping its `Drain` only removes
+//! the values that were taken, so the reference for a `Drain` that took `n`
+//! values is `Vec::drain(..n)`. Leaking a `Drain` leaks everything, as
+//! leaking `Vec::drain(..)` does.
+//!
+//! These run natively with the rest of the tests, and under Miri with fewer
+//! cases:
+//!
+//!     cargo +nightly miri test -p buggy_drain --test differential -- --include-ignored
+//!
+//! Without the `sound` feature, leaking a `Drain` is where the two part ways:
+//! Miri reports the use-after-free, or, if the `Drain` hadn't taken anything
+//! yet, `MyVec` keeps all its values where `Vec` would be empty, as
+//! `leaked_drain_keeps_values_vec_would_lose` shows without any UB. With the
+//! feature, pass `MIRIFLAGS=-Zmiri-ignore-leaks`, since the fix leaks what
+//! the `Drain` held.
+
+use std::mem;
+
+use buggy_drain::MyVec;
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Op {
+    /// Replace both vectors with new ones holding these values.
+    From(Vec<u8>),
+    /// Start draining. Until the `Drain` is forgotten or dropped, `From`
+    /// and `Drain` are skipped, since the `Drain` borrows the vector.
+    Drain,
+    Next,
+    Forget,
+    Drop,
+}
+
+/// Operations, with `Forget` replaced by `Drop` unless `leak` is set.
+fn op(leak: bool) -> impl Strategy<Value = Op> {
+    prop_oneof![
+        vec(any::<u8>(), 0..=4).prop_map(Op::From),
+        Just(Op::Drain),
+        Just(Op::Next),
+        Just(Op::Forget),
+        Just(Op::Drop),
+    ]
+    .prop_map(move |op| match op {
+        Op::Forget if !leak => Op::Drop,
+        op => op,
+    })
+}
+
+fn from(values: &[u8]) -> MyVec<Box<u8>> {
+    fn boxed<const N: usize>(values: &[u8]) -> MyVec<Box<u8>> {
+        MyVec::from(std::array::from_fn::<_, N, _>(|i| Box::new(values[i])))
+    }
+    match values.len() {
+        0 => boxed::<0>(values),
+        1 => boxed::<1>(values),
+        2 => boxed::<2>(values),
+        3 => boxed::<3>(values),
+        4 => boxed::<4>(values),
+        _ => unreachable!("the strategy makes at most 4 values"),
+    }
+}
+
+fn contents(v: &MyVec<Box<u8>>) -> Vec<u8> {
+    (0..v.len()).map(|i| *v[i]).collect()
+}
+
+fn check(ops: Vec<Op>) -> Result<(), TestCaseError> {
+    let mut ops = ops.into_iter();
+    let mut v = from(&[]);
+    let mut reference: Vec<Box<u8>> = Vec::new();
+    while let Some(op) = ops.next() {
+        match op {
+            Op::From(values) => {
+                v = from(&values);
+                reference = values.into_iter().map(Box::new).collect();
+            }
+            Op::Drain => {
+                let mut drain = v.drain();
+                let mut taken = 0;
+                let mut leaked = false;
+                for op in ops.by_ref() {
+                    match op {
+                        Op::Next => {
+                            let expected = reference.get(taken).map(|value| **value);
+                            prop_assert_eq!(drain.next().map(|value| *value), expected);
+                            taken = (taken + 1).min(reference.len());
+                        }
+                        Op::Forget => {
+                            mem::forget(drain);
+                            leaked = true;
+                            break;
+                        }
+                        Op::Drop => break,
+                        Op::From(_) | Op::Drain => {}
+                    }
+                }
+                if leaked {
+                    mem::forget(reference.drain(..));
+                } else {
+                    reference.drain(..taken);
+                }
+            }
+            Op::Next | Op::Forget | Op::Drop => {}
+        }
+        let expected: Vec<u8> = reference.iter().map(|value| **value).collect();
+        prop_assert_eq!(contents(&v), expected);
+    }
+    Ok(())
+}
+
+fn config() -> ProptestConfig {
+    ProptestConfig {
+        // Miri is thousands of times slower than a native build.
+        cases: if cfg!(miri) { 8 } else { 256 },
+        // Miri's isolation doesn't allow saving failures to a file.
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    }
+}
+
+/// The divergence that doesn't need the use-after-free: a `Drain` leaked
+/// before taking anything leaves `MyVec` with all its values, where `Vec`
+/// is left empty. Nothing freed is touched, so this runs under Miri too.
+#[cfg(not(feature = "sound"))]
+#[test]
+fn leaked_drain_keeps_values_vec_would_lose() {
+    let mut v = from(&[1, 2, 3]);
+    mem::forget(v.drain());
+    assert_eq!(contents(&v), [1, 2, 3]);
+
+    let mut reference = vec![1u8, 2, 3];
+    mem::forget(reference.drain(..));
+    assert!(reference.is_empty());
+}
+
+proptest! {
+    #![proptest_config(config())]
+
+    #[test]
+    fn behaves_like_vec(ops in vec(op(false), 0..32)) {
+        check(ops)?;
+    }
+
+    #[cfg_attr(
+        not(feature = "sound"),
+        ignore = "leaking a `Drain` is undefined behavior without the fix"
+    )]
+    #[test]
+    fn behaves_like_vec_when_drain_leaked(ops in vec(op(true), 0..32)) {
+        check(ops)?;
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(invalid_reference_casting)]
+
 #[cfg(test)]
 mod test {
 
@@ -10,6 +12,10 @@ mod test {
         *b += *a;
     }
 
+    #[zoo_harness::expect_ub(
+        kind = "aliasing",
+        message = "but that tag only grants SharedReadOnly permission"
+    )]
     #[test]
     fn illegal_mutable_alias() {
         let mut x = Box::new(3u32);
@@ -18,8 +24,17 @@ mod test {
         // If this alias (a mutable reference) ever exists at the
         // same time as another reference to the same location,
         // undefined behavior results.
+        #[cfg(not(feature = "sound"))]
         let x_alias = x.as_ref() as *const u32 as *mut u32;
-        let mut x_alias = unsafe { &mut *x_alias };
+        #[cfg(not(feature = "sound"))]
+        let x_alias = unsafe { &mut *x_alias };
+
+        // If both arguments really need to start out with the same value,
+        // give the second one a copy.
+        #[cfg(feature = "sound")]
+        let mut x_copy = *x;
+        #[cfg(feature = "sound")]
+        let x_alias = &mut x_copy;
 
         // update_values relies on the fact that it's two arguments don't
         // refer to the same location in memory. Because we violated that
@@ -27,9 +42,12 @@ mod test {
         // outside of Miri).
         // Miri will detect the duplicate mutable borrow, even before making
         // the function call.
-        update_two_ints(&mut x, &mut x_alias);
+        update_two_ints(&mut x, x_alias);
 
+        #[cfg(not(feature = "sound"))]
         assert_eq!(*x, 9);
+        #[cfg(feature = "sound")]
+        assert_eq!((*x, *x_alias), (6, 9));
     }
 
     // This function shows that bad things can happen,
@@ -43,12 +61,20 @@ mod test {
         eprintln!("a: {}, b: {}", a.load(Ordering::SeqCst), *b);
     }
 
+    #[zoo_harness::expect_ub(kind = "aliasing", message = "which is strongly protected")]
     #[test]
     fn atomic_alias() {
         let x = AtomicU32::new(1000);
+        #[cfg(not(feature = "sound"))]
         let x_alias = &x as *const AtomicU32 as *const u32;
+        #[cfg(not(feature = "sound"))]
         let x_alias = unsafe { &*x_alias };
 
+        // Reading the atomic gives us a plain `u32` of our own, which
+        // nothing else can change.
+        #[cfg(feature = "sound")]
+        let x_alias = &x.load(Ordering::SeqCst);
+
         // This triggers undefined behavior, because we are mutating
         // the memory that is observable by a shared reference.
         update_atomic(&x, x_alias);
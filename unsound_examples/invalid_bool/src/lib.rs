@@ -1,14 +1,22 @@
 #![allow(clippy::transmute_int_to_bool)]
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered 0x02, but expected a boolean"
+)]
 #[test]
 fn transmute_to_bool() {
-    use std::intrinsics::transmute;
+    #[cfg(not(feature = "sound"))]
+    use std::mem::transmute;
 
     let x = 2u8;
     // A `bool` may only contain the bit pattern for 0 or 1.
     // Creating this malformed bool is undefined behavior,
     // even if it is never used again.
+    #[cfg(not(feature = "sound"))]
     let _y: bool = unsafe { transmute(x) };
+    #[cfg(feature = "sound")]
+    let _y: bool = x != 0;
 }
 
 // To make this code sound:
@@ -1,11 +1,23 @@
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered 0, but expected something greater or equal to 1"
+)]
 #[test]
 fn null_box() {
     use std::ptr::null_mut;
 
     // A `Box` may not contain a null reference. This is undefined behavior,
     // even if the value is never dereferenced.
+    #[cfg(not(feature = "sound"))]
     let _x: Box<u32> = unsafe { Box::from_raw(null_mut()) };
 
+    #[cfg(feature = "sound")]
+    {
+        let ptr: *mut u32 = null_mut();
+        let x: Option<Box<u32>> = (!ptr.is_null()).then(|| unsafe { Box::from_raw(ptr) });
+        assert!(x.is_none());
+    }
+
     // To make this code sound:
     //
     // If creating a Box from a raw pointer, add checks to ensure the pointer
@@ -15,6 +27,10 @@ fn null_box() {
     // allocation, so that the value can be freed when the Box is dropped.
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered an unaligned box (required 2 byte alignment but found 1)"
+)]
 #[test]
 fn misaligned_box() {
     let valid_box = Box::new(0x07070707u32);
@@ -30,21 +46,33 @@ fn misaligned_box() {
     // This is a bad idea, because it is not properly aligned.
     let u16_ptr = u8_ptr as *mut u16;
 
+    // Reading through the misaligned pointer is fine, as long as we say so
+    // and don't pretend to own the memory. Then free it through the pointer
+    // that does own it.
+    #[cfg(feature = "sound")]
+    {
+        assert_eq!(unsafe { u16_ptr.read_unaligned() }, 0x0707);
+        drop(unsafe { Box::from_raw(u32_ptr) });
+    }
+
     // Convert the u16 pointer into a Box.
     // This is undefined behavior, as a Box may not contain a misaligned
     // pointer.
-    let invalid_box = unsafe { Box::from_raw(u16_ptr) };
-
-    // This assert may crash on architectures that don't allow misaligned
-    // memory access. This may actually work on architectures like x86 that
-    // allow misaligned access, but since we relied on undefined behavior to
-    // get here, we can't make any guarantees about what might happen.
-    assert_eq!(*invalid_box, 0x0707);
-
-    // There's actually a second problem here: when the invalid `Box` goes out
-    // of scope, the bad pointer will attempt to be freed, which will cause
-    // the program to abort. Avoid this for this example by not deallocating.
-    Box::leak(invalid_box);
+    #[cfg(not(feature = "sound"))]
+    {
+        let invalid_box = unsafe { Box::from_raw(u16_ptr) };
+
+        // This assert may crash on architectures that don't allow misaligned
+        // memory access. This may actually work on architectures like x86 that
+        // allow misaligned access, but since we relied on undefined behavior to
+        // get here, we can't make any guarantees about what might happen.
+        assert_eq!(*invalid_box, 0x0707);
+
+        // There's actually a second problem here: when the invalid `Box` goes out
+        // of scope, the bad pointer will attempt to be freed, which will cause
+        // the program to abort. Avoid this for this example by not deallocating.
+        Box::leak(invalid_box);
+    }
 
     // It may not be possible to make this code sound. If it was just pointer
     // alignment, we could perform a safety check and return
@@ -59,34 +87,64 @@ fn misaligned_box() {
     // tricky.
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered a dangling box (0x8888[noalloc] has no provenance)"
+)]
 #[test]
 fn nonsense_box() {
     let ptr = 0x8888 as *mut u32;
     // This is undefined behavior. A `Box` may not contain a pointer that
     // is not a valid address.
-    let x: Box<u32> = unsafe { Box::from_raw(ptr) };
-
-    // If this box is dropped, we will attempt to free a nonsense address
-    // and the program will abort. Avoid this by "leaking" the Box.
-    Box::leak(x);
+    #[cfg(not(feature = "sound"))]
+    {
+        let x: Box<u32> = unsafe { Box::from_raw(ptr) };
+
+        // If this box is dropped, we will attempt to free a nonsense address
+        // and the program will abort. Avoid this by "leaking" the Box.
+        Box::leak(x);
+    }
+
+    // There's no way to check that an arbitrary address is a live
+    // allocation, so the only sound thing to do is to keep it as a raw
+    // pointer and never dereference it.
+    #[cfg(feature = "sound")]
+    assert_eq!(ptr as usize, 0x8888);
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered a dangling box (use-after-free)"
+)]
 #[test]
 fn dangling_box() {
-    use std::ops::DerefMut;
-
-    let mut x = Box::new(0u32);
-
-    // Get a pointer to the value inside x, without consuming the Box.
-    let ptr_x = x.deref_mut() as *mut u32;
-
-    // Create a second Box that "owns" the same pointer as `x`. This will
-    // trigger undefined behavior as soon as either x or y is dropped
-    // (because whichever one remains will contain a pointer to deallocated
-    // memory.
-    let y = unsafe { Box::from_raw(ptr_x) };
-
-    // Manually drop each box so the problem is obvious in Miri.
-    drop(x);
-    drop(y);
+    #[cfg(not(feature = "sound"))]
+    {
+        use std::ops::DerefMut;
+
+        let mut x = Box::new(0u32);
+
+        // Get a pointer to the value inside x, without consuming the Box.
+        let ptr_x = x.deref_mut() as *mut u32;
+
+        // Create a second Box that "owns" the same pointer as `x`. This will
+        // trigger undefined behavior as soon as either x or y is dropped
+        // (because whichever one remains will contain a pointer to deallocated
+        // memory.
+        let y = unsafe { Box::from_raw(ptr_x) };
+
+        // Manually drop each box so the problem is obvious in Miri.
+        drop(x);
+        drop(y);
+    }
+
+    // Consuming the Box hands its ownership to the pointer, and from there
+    // to exactly one new Box.
+    #[cfg(feature = "sound")]
+    {
+        let x = Box::new(0u32);
+        let ptr_x = Box::into_raw(x);
+        let y = unsafe { Box::from_raw(ptr_x) };
+        drop(y);
+    }
 }
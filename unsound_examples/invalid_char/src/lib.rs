@@ -1,12 +1,19 @@
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered 0x0000dd80, but expected a valid unicode scalar value"
+)]
 #[test]
 fn invalid_char() {
     // Creating this invalid char is undefined behavior, even if it is
     // never used again.
+    #[cfg(not(feature = "sound"))]
     let _x = unsafe {
         // This is a surrogate value, which is used in UTF-16 encodings
         // but has no place in a Rust char.
         char::from_u32_unchecked(0x0000dd80)
     };
+    #[cfg(feature = "sound")]
+    assert_eq!(char::from_u32(0x0000dd80), None);
 }
 
 // To make this code sound:
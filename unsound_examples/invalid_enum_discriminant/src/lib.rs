@@ -7,9 +7,14 @@ enum Count {
     Many,
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered 0x04, but expected a valid enum tag"
+)]
 #[test]
 fn enum_bogus_discriminant() {
-    use std::intrinsics::transmute;
+    #[cfg(not(feature = "sound"))]
+    use std::mem::transmute;
 
     let x = 4u8;
 
@@ -20,27 +25,44 @@ fn enum_bogus_discriminant() {
     // Creating this malformed enum is undefined behavior, even if it is
     // never used again.
 
+    #[cfg(not(feature = "sound"))]
     let _y: Count = unsafe { transmute(x) };
+    #[cfg(feature = "sound")]
+    let _y: Result<Count, CountError> = Count::try_from(x);
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered 0x04, but expected a valid enum tag"
+)]
 #[test]
 fn enum_bogus_discriminant2() {
-
     // Creating a malformed enum using a raw pointer instead of transmute.
     // This is also undefined behavior.
-    let y = Count::One;
-    let ptr: *mut Count = &y;
+    let mut y = Count::One;
+    let ptr: *mut Count = &mut y;
+    #[cfg(not(feature = "sound"))]
     unsafe {
         // Write a single byte 4.
         ptr.write_bytes(4, 1);
     }
+    // Only write a whole `Count`, and only once we know the byte is one.
+    #[cfg(feature = "sound")]
+    if let Ok(count) = Count::try_from(4) {
+        unsafe { ptr.write(count) };
+    }
+
+    // Writing the byte isn't enough for Miri to notice: the malformed enum
+    // is produced when `y` is next used as a `Count`.
+    let _z = y;
 }
 
 // How to make this code sound:
 //
 // There are several crates that can help the programmer derive
-// `TryFrom` or something equivalent.
-// FIXME: name those crates
+// `TryFrom` or something equivalent: `num_enum` has
+// `#[derive(TryFromPrimitive)]`, `strum` has `#[derive(FromRepr)]`, and
+// `num-derive` has `#[derive(FromPrimitive)]`.
 //
 // It's also possible to code it yourself, though that's probably not a good
 // idea because if additional variants are later added it would silently break
@@ -1,3 +1,7 @@
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "encountered a dangling reference (going beyond the bounds of its allocation)"
+)]
 #[test]
 fn malformed_slice() {
     use core::slice;
@@ -9,7 +13,10 @@ fn malformed_slice() {
     // initialized) range is undefined behavior, even if it is never
     // used to access memory outside the original String.
 
+    #[cfg(not(feature = "sound"))]
     let _y: &[u8] = unsafe { slice::from_raw_parts(ptr, 100) };
+    #[cfg(feature = "sound")]
+    let _y: &[u8] = unsafe { slice::from_raw_parts(ptr, x.len()) };
 }
 
 // To make this code sound:
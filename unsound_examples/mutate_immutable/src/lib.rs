@@ -5,9 +5,15 @@ mod test {
     use std::thread;
 
     // This test does have a race condition, and it is undefined behavior
-    // according to the Rust reference, but Miri is not able to detect
-    // the problem.
+    // according to the Rust reference, but Miri's data race detector is not
+    // able to see it. What Miri does catch is the write through a pointer
+    // that was derived from a shared reference, which is undefined behavior
+    // even without a second thread.
     //
+    #[zoo_harness::expect_ub(
+        kind = "aliasing",
+        message = "but that tag only grants SharedReadOnly permission"
+    )]
     #[test]
     fn mutate_via_shared_reference() {
         let message = Arc::new(String::from("Hello, world!"));
@@ -18,22 +24,39 @@ mod test {
             eprintln!("message1: {}", message1);
         });
 
-        let message_ref: &str = &*message;
-        let message_ptr = message_ref.as_ptr() as *mut u8;
-        unsafe {
-            message_ptr.write_bytes(b'x', 5);
+        #[cfg(not(feature = "sound"))]
+        {
+            let message_ref: &str = &message;
+            let message_ptr = message_ref.as_ptr() as *mut u8;
+            unsafe {
+                message_ptr.write_bytes(b'x', 5);
+            }
+
+            eprintln!("message: {}", message);
+
+            child.join().unwrap();
         }
 
-        eprintln!("message: {}", message);
+        // Wait until no other thread can see the message; then `Arc` will
+        // hand out a `&mut` to it.
+        #[cfg(feature = "sound")]
+        {
+            child.join().unwrap();
 
-        child.join().unwrap();
+            let mut message = message;
+            Arc::get_mut(&mut message)
+                .unwrap()
+                .replace_range(..5, "xxxxx");
+
+            eprintln!("message: {}", message);
+        }
     }
 
     // To make this code sound:
     //
     // Use std library tools to mutate shared data: Mutex, atomics, etc.
     // Or, use well-trusted third party crates.
-    // 
+    //
     // If you do need to use unsafe to create your own data structure that
     // manages concurrent access, make certain that race invariants are upheld.
 }
@@ -59,5 +82,79 @@ mod tests_that_dont_work {
 
     // To make this code sound:
     //
-    //
+    // A constant can't be mutated at all: each use of it may be a separate
+    // copy, and the one behind `as_ptr` may be in read-only memory. Copy it
+    // into a `String` and mutate that, or, if the whole program needs to see
+    // the change, use a `static` with interior mutability, like a `Mutex`.
+}
+
+// Miri reports undefined behavior in `mutate_via_shared_reference`, but not
+// the data race. With Stacked Borrows on, it stops at the write through
+// `message_ptr`, which was derived from a shared reference; that's the
+// aliasing error in the test's snapshot. With Stacked Borrows off, as in
+// the test's seed sweep, every run is clean. Miri's race detector doesn't
+// look for accesses that overlap in time: it reports two accesses that
+// nothing orders by happens-before, in the one execution each seed
+// produces. In the executions Miri picks, the main thread writes before the
+// child thread gets to run, and both threads take the lock on stderr to
+// print, so the write happens before the read and there's no race to
+// report. The child reading first would be one, but no seed in the sweep
+// has scheduled it that way.
+//
+// Loom runs the same code under every interleaving instead, and tracks
+// every access to the string, so it reports the race deterministically.
+// Loom can only see accesses that go through its own `UnsafeCell`, so this
+// is a model of the test rather than the test itself: the `String` lives in
+// a loom cell, and the write through `message_ptr` is a `with_mut` on it.
+//
+// Run it with:
+//
+//     RUSTFLAGS="--cfg loom" LOOM_LOCATION=1 \
+//         cargo test -p mutate_immutable --release loom
+//
+// `LOOM_LOCATION` makes loom point at the lines of the two racing accesses.
+#[cfg(all(test, loom))]
+mod loom_model {
+    use loom::cell::UnsafeCell;
+    use loom::sync::Arc;
+    use loom::thread;
+
+    struct Message(UnsafeCell<String>);
+
+    // What `mutate_via_shared_reference` assumes: that a `String` shared
+    // between threads may be written through a shared reference.
+    unsafe impl Sync for Message {}
+
+    #[cfg_attr(not(feature = "sound"), should_panic(expected = "Causality"))]
+    #[test]
+    fn mutate_via_shared_reference() {
+        loom::model(|| {
+            let string = String::from("Hello, world!");
+            let message = Arc::new(Message(UnsafeCell::new(string)));
+
+            let message1 = Arc::clone(&message);
+            let read = move || message1.0.with(|s| unsafe { (*s).clone() });
+            let child = thread::spawn(read);
+
+            #[cfg(not(feature = "sound"))]
+            {
+                message
+                    .0
+                    .with_mut(|s| unsafe { (*s).replace_range(..5, "xxxxx") });
+
+                child.join().unwrap();
+            }
+
+            #[cfg(feature = "sound")]
+            {
+                assert_eq!(child.join().unwrap(), "Hello, world!");
+
+                let mut message = message;
+                Arc::get_mut(&mut message)
+                    .unwrap()
+                    .0
+                    .with_mut(|s| unsafe { (*s).replace_range(..5, "xxxxx") });
+            }
+        });
+    }
 }
@@ -1,3 +1,4 @@
+#[zoo_harness::expect_ub(kind = "invalid-value", message = "encountered a null reference")]
 #[test]
 fn null_reference() {
     use std::ptr::null;
@@ -7,5 +8,16 @@ fn null_reference() {
 
     // This is a null reference. This is undefined behavior, even if the
     // value is never read or dereferenced.
+    #[cfg(not(feature = "sound"))]
     let _y: &u32 = unsafe { &*x };
+
+    // `as_ref` checks for null, and gives us an `Option` instead.
+    #[cfg(feature = "sound")]
+    assert_eq!(unsafe { x.as_ref() }, None);
 }
+
+// To make this code sound:
+//
+// Don't turn a raw pointer into a reference unless you know it isn't null.
+// `<*const T>::as_ref` (and `as_mut`) check for null and return an
+// `Option`; `NonNull` keeps the pointer known to be non-null in its type.
@@ -17,57 +17,105 @@ impl StructWithPadding {
             y: (z as u32) << 16,
         }
     }
+
+    // Serialize one field at a time, writing zeroes where the padding is.
+    #[cfg(feature = "sound")]
+    fn to_bytes(&self) -> [u8; 8] {
+        let mut bytes = [0u8; 8];
+        bytes[0] = self.x;
+        bytes[4..].copy_from_slice(&self.y.to_ne_bytes());
+        bytes
+    }
 }
 
+// What `StructWithPadding::new(7)` looks like in memory, padding aside.
+// `y` is stored in the target's byte order.
+#[cfg(target_endian = "little")]
+const SEVEN: [u8; 8] = [7, 0, 0, 0, 0, 0, 7, 0];
+#[cfg(target_endian = "big")]
+const SEVEN: [u8; 8] = [7, 0, 0, 0, 0, 7, 0, 0];
+
+#[zoo_harness::expect_ub(kind = "uninit", message = "memory is uninitialized at [0x1..0x4]")]
 #[test]
 fn read_padding() {
     let data = StructWithPadding::new(7);
 
-    // This is more verbose than necessary, to make it easier to explain.
-    // First, get a pointer to `data`.
-    let data_ptr: *const StructWithPadding = &data;
-    // Then, cast that pointer to a different type. This does not require
-    // `unsafe`!
-    let data_ptr: *const [u8; 8] = data_ptr as *const [u8; 8];
+    // Show which bytes of `data` are padding (bytes 1-3, as described above).
+    zoo_harness::print_layout!(StructWithPadding { x, y });
+
     let mut data_buf = [0u8; 8];
 
-    // Because this reads the padding bytes, this copy is undefined behavior.
-    unsafe { core::ptr::copy_nonoverlapping(data_ptr, &mut data_buf, 1) };
+    #[cfg(not(feature = "sound"))]
+    {
+        // This is more verbose than necessary, to make it easier to explain.
+        // First, get a pointer to `data`.
+        let data_ptr: *const StructWithPadding = &data;
+        // Then, cast that pointer to a different type. This does not require
+        // `unsafe`!
+        let data_ptr: *const [u8; 8] = data_ptr as *const [u8; 8];
+
+        // Because this reads the padding bytes, this copy is undefined behavior.
+        unsafe { core::ptr::copy_nonoverlapping(data_ptr, &mut data_buf, 1) };
+    }
+    #[cfg(feature = "sound")]
+    data_buf.copy_from_slice(&data.to_bytes());
 
     // For some reason, Miri doesn't notice the problem until we read the result.
-    assert_eq!(data_buf, [7, 0, 0, 0, 0, 0, 7, 0]);
+    assert_eq!(data_buf, SEVEN);
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "at [1], encountered uninitialized memory, but expected an integer"
+)]
 #[test]
 fn read_padding2() {
-    use std::intrinsics::transmute;
+    #[cfg(not(feature = "sound"))]
+    use std::mem::transmute;
 
     let data = StructWithPadding::new(7);
 
     // I'm unsure whether this reference is UB?
+    #[cfg(not(feature = "sound"))]
     let data_buf: &[u8; 8] = unsafe { transmute(&data) };
+    #[cfg(feature = "sound")]
+    let data_buf: &[u8; 8] = &data.to_bytes();
 
     // Because this reads the padding bytes, this copy is undefined behavior.
     let data_buf_copy: [u8; 8] = *data_buf;
 
     // For some reason, Miri doesn't notice the problem until we read the result.
-    assert_eq!(data_buf_copy, [7, 0, 0, 0, 0, 0, 7, 0]);
+    assert_eq!(data_buf_copy, SEVEN);
 }
 
+#[zoo_harness::expect_ub(
+    kind = "invalid-value",
+    message = "at [0], encountered uninitialized memory, but expected an integer"
+)]
 #[test]
 fn read_padding3() {
-    use std::intrinsics::transmute;
     use std::mem;
-
-    let mut data = StructWithPadding::new(7);
+    #[cfg(not(feature = "sound"))]
+    use std::mem::transmute;
 
     // Choose this array layout so it has the same alignment as StructWithPadding.
     let mut dest = [0u32; 2];
 
     // Casting to a u64 reference may be UB? (Because there is uninitialized data in there)
     // mem::swap is definitely UB since it will always read the padding bytes.
-    let data_cast: &mut [u32; 2] = unsafe { transmute(&mut data) };
-    mem::swap(data_cast, &mut dest);
+    #[cfg(not(feature = "sound"))]
+    {
+        let mut data = StructWithPadding::new(7);
+        let data_cast: &mut [u32; 2] = unsafe { transmute(&mut data) };
+        mem::swap(data_cast, &mut dest);
+    }
+    // Build the array out of the fields, rather than the struct's bytes.
+    #[cfg(feature = "sound")]
+    {
+        let data = StructWithPadding::new(7);
+        let mut fields = [data.x.into(), data.y];
+        mem::swap(&mut fields, &mut dest);
+    }
 
     // For some reason, Miri doesn't notice the problem until we read the result.
     assert_eq!(dest, [7, 0x70000]);
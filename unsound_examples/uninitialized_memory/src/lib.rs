@@ -4,35 +4,54 @@ mod test {
     use std::alloc::Layout;
     use std::mem::MaybeUninit;
 
+    #[zoo_harness::expect_ub(kind = "unreachable", message = "`assume` called with `false`")]
     #[test]
     fn incorrect_vec() {
         let v = Vec::<u32>::with_capacity(4);
 
         // This accesses the 4th element of the vector, which has been
         // allocated but is uninitialized. This is undefined behavior.
+        #[cfg(not(feature = "sound"))]
         let value = unsafe { v.get_unchecked(3) };
+        #[cfg(not(feature = "sound"))]
         eprintln!("vec value: {}", value);
 
+        // `get` checks the length, not the capacity.
+        #[cfg(feature = "sound")]
+        assert_eq!(v.get(3), None);
+
         // Vec has many other unsafe fns that can result in access
         // to uninitialized memory (e.g. set_len, as_chunks_unchecked).
         // Misuse of any of them can result in undefined behavior.
     }
 
+    #[zoo_harness::expect_ub(kind = "uninit", message = "memory is uninitialized at [0x0..0x4]")]
     #[test]
     fn misused_allocate() {
         // It should be quite rare to need to allocate memory in this
         // way. Prefer using MaybeUninit instead.
 
         let layout = Layout::new::<u32>();
+        #[cfg(not(feature = "sound"))]
         let heap_u32 = unsafe { std::alloc::alloc(layout) as *mut u32 };
 
+        // `alloc_zeroed` hands back memory that is initialized, to zero.
+        #[cfg(feature = "sound")]
+        let heap_u32 = unsafe { std::alloc::alloc_zeroed(layout) as *mut u32 };
+
         // This is undefined behavior because we're directly reading from
         // a pointer, that points to uninitialized memory.
         let value = unsafe { *heap_u32 };
 
         eprintln!("allocated value: {}", value);
+
+        #[cfg(feature = "sound")]
+        unsafe {
+            std::alloc::dealloc(heap_u32 as *mut u8, layout)
+        };
     }
 
+    #[zoo_harness::expect_ub(kind = "uninit", message = "memory is uninitialized at [0x0..0x4]")]
     #[test]
     fn bad_assume_init() {
         // This is how MaybeUninit should be used; after a value has been
@@ -44,7 +63,11 @@ mod test {
 
         // This is undefined behavior; it is incorrect to call assume_init()
         // here because we have never actually loaded a value into x.
+        #[cfg(not(feature = "sound"))]
         let y = MaybeUninit::<u32>::uninit();
+        // Zeroes are a valid `u32`, so this one really is initialized.
+        #[cfg(feature = "sound")]
+        let y = MaybeUninit::<u32>::zeroed();
         let bad_y = unsafe { y.assume_init() };
 
         println!("x: {}, y: {}", good_x, bad_y);
@@ -54,3 +77,69 @@ mod test {
     //
     // Don't ever access memory that could be uninitialized.
 }
+
+// Miri only checks the values a test happens to use. These Kani harnesses
+// check each pattern for every length, capacity and index up to a bound.
+// Run them with `-Z uninit-checks`, so Kani tracks which bytes have been
+// initialized: with `--features sound` they verify, and without it Kani
+// reports the read of uninitialized memory.
+#[cfg(kani)]
+mod proofs {
+    use std::alloc::Layout;
+    use std::mem::MaybeUninit;
+
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn vec_index_within_capacity() {
+        let capacity: usize = kani::any();
+        kani::assume(capacity <= 4);
+        let mut v = Vec::<u32>::with_capacity(capacity);
+        let length: usize = kani::any();
+        kani::assume(length <= capacity);
+        for _ in 0..length {
+            v.push(kani::any());
+        }
+        let index: usize = kani::any();
+        kani::assume(index < capacity);
+
+        #[cfg(not(feature = "sound"))]
+        let value = Some(unsafe { *v.get_unchecked(index) });
+        #[cfg(feature = "sound")]
+        let value = v.get(index).copied();
+
+        assert_eq!(value.is_some(), index < length);
+    }
+
+    #[kani::proof]
+    fn read_allocation() {
+        let layout = Layout::new::<u32>();
+        #[cfg(not(feature = "sound"))]
+        let heap_u32 = unsafe { std::alloc::alloc(layout) as *mut u32 };
+        #[cfg(feature = "sound")]
+        let heap_u32 = unsafe { std::alloc::alloc_zeroed(layout) as *mut u32 };
+        // Some callers write a value first; the ones that don't are the bug.
+        let written: Option<u32> = kani::any();
+        if let Some(value) = written {
+            unsafe { heap_u32.write(value) };
+        }
+
+        let value = unsafe { *heap_u32 };
+        assert_eq!(value, written.unwrap_or(0));
+        unsafe { std::alloc::dealloc(heap_u32 as *mut u8, layout) };
+    }
+
+    #[kani::proof]
+    fn assume_init() {
+        #[cfg(not(feature = "sound"))]
+        let mut x = MaybeUninit::<u32>::uninit();
+        #[cfg(feature = "sound")]
+        let mut x = MaybeUninit::<u32>::zeroed();
+        let written: Option<u32> = kani::any();
+        if let Some(value) = written {
+            x.write(value);
+        }
+
+        let value = unsafe { x.assume_init() };
+        assert_eq!(value, written.unwrap_or(0));
+    }
+}
@@ -0,0 +1,224 @@
+//! `catalog.json`: every example and test with its permanent ID, for other
+//! tools (and review bots) to link to.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use zoo_harness::Expectation;
+
+use crate::example::{Zoo, EXAMPLES_DIR, REPOSITORY};
+use crate::metadata::{is_example_id, Category};
+use crate::Result;
+
+/// The catalog's file name, at the workspace root.
+pub const CATALOG: &str = "catalog.json";
+
+/// Bumped whenever a field is removed or changes meaning.
+pub const VERSION: u32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Catalog {
+    pub version: u32,
+    pub repository: &'static str,
+    pub examples: Vec<ExampleEntry>,
+    /// Old names, and the IDs they refer to now.
+    pub redirects: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExampleEntry {
+    pub id: String,
+    /// The directory name.
+    pub name: String,
+    pub package: String,
+    pub title: String,
+    pub category: &'static str,
+    pub ub_class: String,
+    pub miri_detects: bool,
+    pub reference: String,
+    /// The source file, relative to the repository root.
+    pub source: String,
+    pub url: String,
+    pub tests: Vec<TestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestEntry {
+    pub id: String,
+    /// The name libtest knows the test by.
+    pub path: String,
+    /// The first and last line of the test, attributes included.
+    pub lines: [usize; 2],
+    pub url: String,
+    /// What the test declares: `"clean"`, or the UB kind and message. `null`
+    /// for a test that declares nothing.
+    pub expects: Option<Expects>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expects {
+    /// Miri should find nothing.
+    Clean,
+    #[serde(untagged)]
+    Ub(ExpectedUb),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectedUb {
+    pub kind: String,
+    pub message: String,
+}
+
+/// Build the catalog, checking that every example and test has an ID, that
+/// no two share one, and that every redirect leads somewhere.
+pub fn catalog(zoo: &Zoo) -> Result<Catalog> {
+    let mut ids = BTreeSet::new();
+    let mut examples = Vec::new();
+    for example in &zoo.examples {
+        let metadata = example
+            .metadata
+            .as_ref()
+            .ok_or_else(|| format!("`{}` has no [package.metadata.zoo] section", example.name))?;
+        if !ids.insert(metadata.id.clone()) {
+            return Err(format!("`{}` reuses the ID {}", example.name, metadata.id).into());
+        }
+
+        let source = format!("{EXAMPLES_DIR}/{}/src/lib.rs", example.name);
+        let mut tests = Vec::new();
+        for test in &example.source.tests {
+            let id = metadata.test_ids.get(&test.path).ok_or_else(|| {
+                format!("`{}::{}` has no ID in test-ids", example.name, test.path)
+            })?;
+            let suffix = id.strip_prefix(&format!("{}.", metadata.id));
+            if !suffix.is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())) {
+                return Err(format!(
+                    "`{}::{}` has the ID {id}, which isn't {}.<number>",
+                    example.name, test.path, metadata.id
+                )
+                .into());
+            }
+            if !ids.insert(id.clone()) {
+                return Err(format!("`{}::{}` reuses the ID {id}", example.name, test.path).into());
+            }
+
+            let (start, end) = (*test.lines.start(), *test.lines.end());
+            tests.push(TestEntry {
+                id: id.clone(),
+                path: test.path.clone(),
+                lines: [start, end],
+                url: format!("{}#L{start}-L{end}", example.source_url()),
+                expects: test
+                    .expectation
+                    .as_ref()
+                    .map(|expectation| match expectation {
+                        Expectation::Ub { kind, message } => Expects::Ub(ExpectedUb {
+                            kind: kind.to_string(),
+                            message: message.clone(),
+                        }),
+                        Expectation::Clean => Expects::Clean,
+                    }),
+            });
+        }
+
+        examples.push(ExampleEntry {
+            id: metadata.id.clone(),
+            name: example.name.clone(),
+            package: example.package.clone(),
+            title: metadata.title.clone(),
+            category: match metadata.category {
+                Category::Simple => "simple",
+                Category::Complex => "complex",
+            },
+            ub_class: metadata.ub_class.to_string(),
+            miri_detects: metadata.miri_detects,
+            reference: metadata.reference.clone(),
+            source,
+            url: example.source_url(),
+            tests,
+        });
+    }
+    examples.sort_by(|a, b| a.id.cmp(&b.id));
+
+    for (name, id) in &zoo.redirects {
+        let example_id = id
+            .split_once('.')
+            .map_or(id.as_str(), |(example, _)| example);
+        if !is_example_id(example_id) || !ids.contains(id) {
+            return Err(
+                format!("the redirect from `{name}` leads to {id}, which doesn't exist").into(),
+            );
+        }
+    }
+
+    Ok(Catalog {
+        version: VERSION,
+        repository: REPOSITORY,
+        examples,
+        redirects: zoo.redirects.clone(),
+    })
+}
+
+/// The contents of `catalog.json`.
+pub fn render(zoo: &Zoo) -> Result<String> {
+    let mut json = serde_json::to_string_pretty(&catalog(zoo)?)?;
+    json.push('\n');
+    Ok(json)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn catalog_is_up_to_date() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let catalog = std::fs::read_to_string(root.join(CATALOG)).unwrap();
+        assert!(
+            catalog == render(&zoo).unwrap(),
+            "{CATALOG} is out of date; run `cargo zoo catalog`"
+        );
+    }
+
+    #[test]
+    fn resolves_ids_and_redirects() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+
+        let (example, test) = zoo.resolve("UZ-0007").unwrap();
+        assert_eq!(example.name, "invalid_enum_discriminant");
+        assert!(test.is_none());
+
+        let (example, test) = zoo.resolve("UZ-0005.4").unwrap();
+        assert_eq!(example.name, "invalid_box");
+        assert_eq!(test.unwrap().path, "dangling_box");
+
+        let (example, _) = zoo.resolve("transmute_bool").unwrap();
+        assert_eq!(example.name, "invalid_bool");
+
+        assert!(zoo.resolve("UZ-0005.9").is_err());
+    }
+
+    #[test]
+    fn tells_clean_from_undeclared() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let catalog = serde_json::to_value(catalog(&zoo).unwrap()).unwrap();
+        let expects = |path: &str| {
+            catalog["examples"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .flat_map(|example| example["tests"].as_array().unwrap())
+                .find(|test| test["path"] == path)
+                .unwrap()["expects"]
+                .clone()
+        };
+        assert_eq!(expects("see_vec_works"), "clean");
+        assert!(expects("tests_that_dont_work::mutate_constant").is_null());
+        assert_eq!(expects("fails_when_drain_leaked")["kind"], "invalid-value");
+    }
+}
@@ -0,0 +1,588 @@
+//! Structural checks on the examples: the conventions that every example is
+//! meant to follow, but that nothing else enforces.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use zoo_harness::Expectation;
+
+use crate::catalog;
+use crate::example::{Example, Zoo, REPOSITORY};
+use crate::snapshot;
+use crate::source::is_soundness_heading;
+use crate::Result;
+
+/// The documents whose links are checked.
+pub const DOCUMENTS: [&str; 2] = ["README.md", "introduction.md"];
+
+/// Words that mark unfinished work in an example's source.
+const MARKERS: [&str; 3] = ["FIXME", "TODO", "XXX"];
+
+/// Something that doesn't follow the conventions.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Problem {
+    /// The file at fault, relative to the workspace root.
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(path: impl Into<PathBuf>, line: Option<usize>, message: impl Into<String>) -> Problem {
+        Problem {
+            path: path.into(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{line}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Check every example, and the links in the documents at the root.
+pub fn check(zoo: &Zoo) -> Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    for example in &zoo.examples {
+        check_example(zoo, example, &mut problems)?;
+    }
+    // IDs are checked across examples, by building the catalog.
+    if let Err(error) = catalog::catalog(zoo) {
+        problems.push(Problem::new(catalog::CATALOG, None, error.to_string()));
+    }
+    for document in DOCUMENTS {
+        check_links(zoo, document, &mut problems)?;
+    }
+    problems.sort();
+    Ok(problems)
+}
+
+fn check_example(zoo: &Zoo, example: &Example, problems: &mut Vec<Problem>) -> Result<()> {
+    let manifest_path = example.dir.join("Cargo.toml");
+    let manifest = zoo.relative(&manifest_path).to_owned();
+    let source = zoo.relative(&example.source.path).to_owned();
+
+    if example.package != example.name {
+        problems.push(Problem::new(
+            &manifest,
+            None,
+            format!(
+                "the package is named `{}`, but its directory is `{}`",
+                example.package, example.name
+            ),
+        ));
+    }
+
+    let text = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("reading {}: {e}", manifest_path.display()))?;
+    let toml: toml::Table =
+        toml::from_str(&text).map_err(|e| format!("parsing {}: {e}", manifest_path.display()))?;
+    let has_sound_feature = toml
+        .get("features")
+        .and_then(|features| features.get("sound"))
+        .is_some();
+    if !has_sound_feature {
+        problems.push(Problem::new(&manifest, None, "there is no `sound` feature"));
+    }
+
+    check_source(example, &source, problems);
+
+    let Some(metadata) = &example.metadata else {
+        problems.push(Problem::new(
+            &manifest,
+            None,
+            "there is no [package.metadata.zoo] section",
+        ));
+        return Ok(());
+    };
+    if metadata.title.trim().is_empty() {
+        problems.push(Problem::new(&manifest, None, "the title is empty"));
+    }
+    if !metadata
+        .reference
+        .starts_with("https://doc.rust-lang.org/reference/")
+    {
+        problems.push(Problem::new(
+            &manifest,
+            None,
+            format!(
+                "the reference `{}` isn't a link into the Rust Reference",
+                metadata.reference
+            ),
+        ));
+    }
+
+    let tests: Vec<&str> = example
+        .source
+        .tests
+        .iter()
+        .map(|t| t.path.as_str())
+        .collect();
+    let listed: Vec<&str> = metadata.tests.iter().map(String::as_str).collect();
+    for test in &tests {
+        if !listed.contains(test) {
+            problems.push(Problem::new(
+                &manifest,
+                None,
+                format!("`{test}` is missing from `tests`"),
+            ));
+        }
+        if !metadata.test_ids.contains_key(*test) {
+            problems.push(Problem::new(
+                &manifest,
+                None,
+                format!("`{test}` has no ID in `test-ids`"),
+            ));
+        }
+    }
+
+    let known: BTreeSet<&str> = tests.iter().copied().collect();
+    let mut stale = |table: &str, keys: Vec<&String>| {
+        for key in keys {
+            if !known.contains(key.as_str()) {
+                problems.push(Problem::new(
+                    &manifest,
+                    None,
+                    format!("`{table}` lists `{key}`, which isn't one of the tests"),
+                ));
+            }
+        }
+    };
+    stale("tests", metadata.tests.iter().collect());
+    stale("test-ids", metadata.test_ids.keys().collect());
+    stale("models", metadata.models.keys().collect());
+    stale("sanitizers", metadata.sanitizers.keys().collect());
+    stale("targets", metadata.targets.keys().collect());
+    stale("toolchains", metadata.toolchains.keys().collect());
+    stale("seeds", metadata.seeds.keys().collect());
+
+    for test in &example.source.tests {
+        if matches!(test.expectation, Some(Expectation::Ub { .. }))
+            && !snapshot::path(example, test).is_file()
+        {
+            problems.push(Problem::new(
+                &source,
+                Some(*test.lines.start()),
+                format!(
+                    "`{}` has no snapshot; run `cargo zoo snapshot --bless {}::{}`",
+                    test.path, example.name, test.path
+                ),
+            ));
+        }
+    }
+    for orphan in snapshot::orphans(example)? {
+        problems.push(Problem::new(
+            zoo.relative(&orphan.path),
+            None,
+            "the snapshot doesn't belong to any test",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks on an example's `lib.rs`: every test declares what it expects and
+/// has notes on how to make it sound, and nothing is left unfinished.
+fn check_source(example: &Example, source: &Path, problems: &mut Vec<Problem>) {
+    let lines: Vec<&str> = example.source.text.lines().collect();
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        if is_soundness_heading(line) {
+            let has_notes = lines[index + 1..]
+                .iter()
+                .map_while(|line| line.trim_start().strip_prefix("//"))
+                .any(|line| !line.trim().is_empty());
+            if !has_notes {
+                problems.push(Problem::new(
+                    source,
+                    Some(number),
+                    "the \"To make this code sound\" section is empty",
+                ));
+            }
+        }
+        // Only whole-line comments, so that a `//` in a URL or a string
+        // doesn't count.
+        if let Some(comment) = line.trim_start().strip_prefix("//") {
+            for marker in MARKERS {
+                if comment.contains(marker) {
+                    problems.push(Problem::new(
+                        source,
+                        Some(number),
+                        format!("unfinished: `{}`", comment.trim()),
+                    ));
+                }
+            }
+        }
+    }
+
+    for test in &example.source.tests {
+        let line = Some(*test.lines.start());
+        if test.expectation.is_none() && !test.ignored {
+            problems.push(Problem::new(
+                source,
+                line,
+                format!(
+                    "`{}` declares neither #[expect_ub] nor #[expect_clean]",
+                    test.path
+                ),
+            ));
+        }
+        let in_test = example
+            .source
+            .lines(test.lines.clone())
+            .into_iter()
+            .any(is_soundness_heading);
+        if !in_test && example.source.soundness_notes(test).is_none() {
+            problems.push(Problem::new(
+                source,
+                line,
+                format!("`{}` has no \"To make this code sound\" section", test.path),
+            ));
+        }
+    }
+}
+
+/// Check that every link in `document` to a file in the repository leads to
+/// one that exists (and to a heading that exists, for links into Markdown
+/// files), and that the README links to every example.
+fn check_links(zoo: &Zoo, document: &str, problems: &mut Vec<Problem>) -> Result<()> {
+    static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\]\(([^)\s]+)\)").unwrap());
+    let blob = format!("{REPOSITORY}/blob/main/");
+    let tree = format!("{REPOSITORY}/tree/main/");
+
+    let path = zoo.root.join(document);
+    let text =
+        std::fs::read_to_string(&path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+    for (index, line) in text.lines().enumerate() {
+        for link in LINK.captures_iter(line) {
+            let link = &link[1];
+            let local = if let Some(path) = link.strip_prefix(&blob) {
+                path
+            } else if let Some(path) = link.strip_prefix(&tree) {
+                path
+            } else if link.contains("://") || link.starts_with("mailto:") {
+                continue;
+            } else {
+                link
+            };
+            let (file, anchor) = match local.split_once('#') {
+                Some((file, anchor)) => (file, Some(anchor)),
+                None => (local, None),
+            };
+
+            let target = if file.is_empty() {
+                path.clone()
+            } else {
+                zoo.root.join(file)
+            };
+            if !target.exists() {
+                problems.push(Problem::new(
+                    document,
+                    Some(index + 1),
+                    format!("the link to `{link}` leads nowhere"),
+                ));
+                continue;
+            }
+            let Some(anchor) = anchor else { continue };
+            if target.extension().is_some_and(|ext| ext == "md") {
+                let target_text = std::fs::read_to_string(&target)
+                    .map_err(|e| format!("reading {}: {e}", target.display()))?;
+                if !headings(&target_text).any(|heading| heading == anchor) {
+                    problems.push(Problem::new(
+                        document,
+                        Some(index + 1),
+                        format!("the link to `{link}` names a heading that doesn't exist"),
+                    ));
+                }
+            }
+        }
+    }
+
+    if document == "README.md" {
+        for example in &zoo.examples {
+            if !text.contains(&format!("({})", example.source_url())) {
+                problems.push(Problem::new(
+                    document,
+                    None,
+                    format!("`{}` isn't linked; run `cargo zoo readme`", example.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The anchors GitHub gives a Markdown file's headings.
+fn headings(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.lines().filter_map(|line| {
+        let heading = line.strip_prefix('#')?.trim_start_matches('#').trim();
+        Some(
+            heading
+                .to_lowercase()
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == ' ' || *c == '-' || *c == '_')
+                .map(|c| if c == ' ' { '-' } else { c })
+                .collect(),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "sample"
+version = "0.1.0"
+edition = "2021"
+
+[features]
+sound = []
+
+[package.metadata.zoo]
+id = "UZ-0001"
+title = "A sample"
+category = "simple"
+order = 1
+ub-class = "invalid-value"
+miri-detects = true
+reference = "https://doc.rust-lang.org/reference/behavior-considered-undefined.html"
+tests = ["sample"]
+test-ids = { sample = "UZ-0001.1" }
+"#;
+
+    const SOURCE: &str = r#"#[zoo_harness::expect_ub(kind = "invalid-value", message = "oops")]
+#[test]
+fn sample() {
+    // Not a bug, just a link: https://example.com/
+    let _url = "https://example.com/TODO";
+}
+
+// To make this code sound:
+//
+// Don't.
+"#;
+
+    /// A one-example zoo in a directory of its own, removed when dropped.
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Fixture {
+            let root =
+                std::env::temp_dir().join(format!("zoo-check-{name}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&root);
+            let fixture = Fixture { root };
+            fixture.write("unsound_examples/sample/Cargo.toml", MANIFEST);
+            fixture.write("unsound_examples/sample/src/lib.rs", SOURCE);
+            fixture.write("unsound_examples/sample/snapshots/sample.stderr", "error\n");
+            fixture.write(
+                "README.md",
+                &format!("[A sample]({REPOSITORY}/blob/main/unsound_examples/sample/src/lib.rs)\n"),
+            );
+            fixture.write("introduction.md", "# Introduction\n");
+            fixture
+        }
+
+        fn write(&self, path: &str, text: &str) {
+            let path = self.root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+
+        /// Apply `edit` to one of the fixture's files.
+        fn edit(&self, path: &str, edit: impl FnOnce(&str) -> String) {
+            let text = std::fs::read_to_string(self.root.join(path)).unwrap();
+            self.write(path, &edit(&text));
+        }
+
+        fn problems(&self) -> Vec<String> {
+            let zoo = Zoo::load(&self.root).unwrap();
+            check(&zoo)
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    const MANIFEST_PATH: &str = "unsound_examples/sample/Cargo.toml";
+    const SOURCE_PATH: &str = "unsound_examples/sample/src/lib.rs";
+
+    #[test]
+    fn zoo_checks_out() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let problems: Vec<String> = check(&zoo)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert!(problems.is_empty(), "{}", problems.join("\n"));
+    }
+
+    #[test]
+    fn makes_anchors() {
+        let text = "# Intro\n\n### What does \"sound\" and `unsound` mean?\nnot a heading\n";
+        assert_eq!(
+            headings(text).collect::<Vec<_>>(),
+            ["intro", "what-does-sound-and-unsound-mean"]
+        );
+    }
+
+    #[test]
+    fn fixture_checks_out() {
+        // Including the `TODO` in a URL in a string, which isn't a comment.
+        assert_eq!(Fixture::new("clean").problems(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn flags_the_manifest() {
+        let fixture = Fixture::new("manifest");
+        fixture.edit(MANIFEST_PATH, |text| {
+            text.replace("name = \"sample\"", "name = \"other\"")
+                .replace("[features]\nsound = []\n", "")
+        });
+        assert_eq!(
+            fixture.problems(),
+            [
+                format!(
+                    "{MANIFEST_PATH}: the package is named `other`, but its directory is `sample`"
+                ),
+                format!("{MANIFEST_PATH}: there is no `sound` feature"),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_stale_test_lists() {
+        let fixture = Fixture::new("stale");
+        fixture.edit(MANIFEST_PATH, |text| {
+            text.replace("tests = [\"sample\"]", "tests = [\"sample\", \"gone\"]")
+                .replace(
+                    "test-ids = { sample = \"UZ-0001.1\" }",
+                    "test-ids = { sample = \"UZ-0001.1\", gone = \"UZ-0001.2\" }",
+                )
+        });
+        assert_eq!(
+            fixture.problems(),
+            [
+                format!("{MANIFEST_PATH}: `test-ids` lists `gone`, which isn't one of the tests"),
+                format!("{MANIFEST_PATH}: `tests` lists `gone`, which isn't one of the tests"),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_missing_notes() {
+        let fixture = Fixture::new("empty-notes");
+        fixture.edit(SOURCE_PATH, |text| text.replace("// Don't.\n", ""));
+        assert_eq!(
+            fixture.problems(),
+            [
+                format!("{SOURCE_PATH}:1: `sample` has no \"To make this code sound\" section"),
+                format!("{SOURCE_PATH}:8: the \"To make this code sound\" section is empty"),
+            ]
+        );
+
+        let fixture = Fixture::new("no-notes");
+        fixture.edit(SOURCE_PATH, |text| {
+            text.split("\n// To make").next().unwrap().to_owned()
+        });
+        assert_eq!(
+            fixture.problems(),
+            [format!(
+                "{SOURCE_PATH}:1: `sample` has no \"To make this code sound\" section"
+            )]
+        );
+    }
+
+    #[test]
+    fn flags_unfinished_work() {
+        let fixture = Fixture::new("markers");
+        fixture.edit(SOURCE_PATH, |text| {
+            text.replace(
+                "    let _url",
+                "    // FIXME: say what goes wrong\n    let _url",
+            )
+        });
+        assert_eq!(
+            fixture.problems(),
+            [format!(
+                "{SOURCE_PATH}:5: unfinished: `FIXME: say what goes wrong`"
+            )]
+        );
+    }
+
+    #[test]
+    fn flags_undeclared_tests() {
+        let fixture = Fixture::new("undeclared");
+        fixture.edit(SOURCE_PATH, |text| {
+            text.split_once('\n').unwrap().1.to_owned()
+        });
+        assert_eq!(
+            fixture.problems(),
+            [format!(
+                "{SOURCE_PATH}:1: `sample` declares neither #[expect_ub] nor #[expect_clean]"
+            )]
+        );
+    }
+
+    #[test]
+    fn flags_snapshots() {
+        let fixture = Fixture::new("snapshots");
+        std::fs::rename(
+            fixture
+                .root
+                .join("unsound_examples/sample/snapshots/sample.stderr"),
+            fixture
+                .root
+                .join("unsound_examples/sample/snapshots/gone.stderr"),
+        )
+        .unwrap();
+        assert_eq!(
+            fixture.problems(),
+            [
+                "unsound_examples/sample/snapshots/gone.stderr: the snapshot doesn't belong to any test"
+                    .to_owned(),
+                format!(
+                    "{SOURCE_PATH}:1: `sample` has no snapshot; run `cargo zoo snapshot --bless sample::sample`"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_broken_links() {
+        let fixture = Fixture::new("links");
+        fixture.write(
+            "README.md",
+            "[Intro](introduction.md#introduction)\n[Missing](missing.md)\n[Heading](introduction.md#nowhere)\n",
+        );
+        assert_eq!(
+            fixture.problems(),
+            [
+                "README.md: `sample` isn't linked; run `cargo zoo readme`".to_owned(),
+                "README.md:2: the link to `missing.md` leads nowhere".to_owned(),
+                "README.md:3: the link to `introduction.md#nowhere` names a heading that doesn't exist"
+                    .to_owned(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! Matching a Miri error from some other codebase against the diagnostics the
+//! zoo's own tests produce, to find the examples that show the same bug.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use zoo_harness::UbKind;
+
+use crate::example::Zoo;
+use crate::miri::{parse_diagnostic, Diagnostic};
+use crate::snapshot;
+use crate::Result;
+
+/// How much each part of a diagnostic counts towards a match. The kind of UB
+/// is the coarsest signal; the wording of the message is the most telling.
+const KIND_WEIGHT: f64 = 0.35;
+const MESSAGE_WEIGHT: f64 = 0.45;
+const NOTES_WEIGHT: f64 = 0.2;
+
+/// Matches scoring below this aren't worth showing.
+pub const THRESHOLD: f64 = 0.4;
+
+/// The parts of a Miri diagnostic that say what went wrong, with everything
+/// specific to one program (types, addresses, tags, paths) left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub kind: UbKind,
+    /// The distinctive words of the message.
+    pub words: BTreeSet<String>,
+    /// Borrow-tracking notes ("<TAG> was created by a SharedReadOnly retag")
+    /// and help text ("the Stacked Borrows rules it violated are still
+    /// experimental"), one per line.
+    pub notes: BTreeSet<String>,
+}
+
+/// One of the zoo's tests, and the signature of the diagnostic recorded in
+/// its snapshot.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// The test's permanent ID, e.g. `UZ-0001.1`.
+    pub id: String,
+    /// The test, as `example::test`.
+    pub test: String,
+    pub message: String,
+    pub signature: Signature,
+}
+
+/// How closely a diagnostic matches one rule.
+#[derive(Debug, Clone)]
+pub struct Match<'a> {
+    pub rule: &'a Rule,
+    /// From 0 (nothing in common) to 1 (the same diagnostic).
+    pub score: f64,
+    pub same_kind: bool,
+    /// How many of the message words the two have in common, from 0 to 1.
+    pub message: f64,
+    pub notes: (usize, usize),
+}
+
+impl Signature {
+    pub fn new(diagnostic: &Diagnostic) -> Signature {
+        static TYPE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r" of type .*?: ").unwrap());
+        static WORD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"[A-Za-z][A-Za-z-]+").unwrap());
+        static SPECIFICS: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"\bALLOC|\[[^\]]*\]|`[^`]*`|0x[0-9a-f]+|\d+").unwrap());
+        const STOP_WORDS: &[&str] = &["and", "are", "but", "for", "its", "the", "this", "was"];
+
+        // The type in "constructing invalid value of type Foo: ..." says more
+        // about the program than about the bug.
+        let message = TYPE.replace(&diagnostic.message, ": ");
+        let message = SPECIFICS.replace_all(&message, " ");
+        let words = WORD
+            .find_iter(&message)
+            .map(|word| word.as_str().to_lowercase())
+            .filter(|word| word.len() > 2 && !STOP_WORDS.contains(&word.as_str()))
+            .collect();
+
+        let notes = diagnostic
+            .text
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim_start().trim_start_matches("= ");
+                let note = line.strip_prefix("help: ")?;
+                // Links to documentation are the same for every diagnostic
+                // of a family, and already covered by the rest of its help.
+                if note.starts_with("see ") {
+                    return None;
+                }
+                let note = SPECIFICS.replace_all(note, " ");
+                let note = note.split_whitespace().collect::<Vec<_>>().join(" ");
+                Some(note.trim_end_matches(" at offsets").to_owned())
+            })
+            .collect();
+
+        Signature {
+            kind: UbKind::classify(&diagnostic.message),
+            words,
+            notes,
+        }
+    }
+}
+
+/// Build a rule from every test with a recorded diagnostic.
+pub fn rules(zoo: &Zoo) -> Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    for example in &zoo.examples {
+        for test in &example.source.tests {
+            let path = snapshot::path(example, test);
+            let text = match std::fs::read_to_string(&path) {
+                Ok(text) => text,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("reading {}: {e}", path.display()).into()),
+            };
+            let Some(diagnostic) = parse_diagnostic(&text) else {
+                continue;
+            };
+            let id = example
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.test_ids.get(&test.path))
+                .cloned()
+                .unwrap_or_default();
+            rules.push(Rule {
+                id,
+                test: format!("{}::{}", example.name, test.path),
+                signature: Signature::new(&diagnostic),
+                message: diagnostic.message,
+            });
+        }
+    }
+    Ok(rules)
+}
+
+/// Every Miri "Undefined Behavior" error in a log, which may hold the output
+/// of several test binaries.
+pub fn diagnostics(log: &str, root: &Path) -> Vec<Diagnostic> {
+    // `normalize` stops at cargo's "test failed" line, which comes after each
+    // failing test binary, so normalize the binaries one at a time.
+    let mut diagnostics = Vec::new();
+    for chunk in log.split("\nerror: test failed") {
+        let chunk = snapshot::normalize(chunk, root);
+        let mut rest = chunk.as_str();
+        while let Some(diagnostic) = parse_diagnostic(rest) {
+            let end = rest.find(&diagnostic.text).unwrap() + diagnostic.text.len();
+            rest = &rest[end..];
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// The rules that match `diagnostic`, best first.
+pub fn classify<'a>(diagnostic: &Diagnostic, rules: &'a [Rule]) -> Vec<Match<'a>> {
+    let signature = Signature::new(diagnostic);
+    let mut matches: Vec<Match> = rules
+        .iter()
+        .map(|rule| {
+            let same_kind = rule.signature.kind == signature.kind;
+            let message = overlap(&rule.signature.words, &signature.words);
+            let shared_notes = rule.signature.notes.intersection(&signature.notes).count();
+            let all_notes = rule.signature.notes.union(&signature.notes).count();
+            let notes = if all_notes == 0 {
+                1.0
+            } else {
+                shared_notes as f64 / all_notes as f64
+            };
+            Match {
+                rule,
+                score: KIND_WEIGHT * f64::from(u8::from(same_kind))
+                    + MESSAGE_WEIGHT * message
+                    + NOTES_WEIGHT * notes,
+                same_kind,
+                message,
+                notes: (shared_notes, rule.signature.notes.len()),
+            }
+        })
+        .filter(|m| m.score >= THRESHOLD)
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.rule.id.cmp(&b.rule.id)));
+    matches
+}
+
+/// Jaccard similarity: how much two sets of words have in common.
+fn overlap(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LOG: &str = "\
+   Compiling shapes v0.3.1 (/home/someone/shapes)
+     Running unittests src/lib.rs (target/miri/x86_64-unknown-linux-gnu/debug/deps/shapes-0123456789abcdef)
+error: Undefined Behavior: attempting a write access using <4021> at alloc1377[0x8], but that tag only grants SharedReadOnly permission for this location
+  --> src/polygon.rs:88:9
+   |
+88 |         (*points).push(p);
+   |         ^^^^^^^^^^^^^^^^^ this error occurs as part of an access at alloc1377[0x8..0x10]
+   |
+   = help: this indicates a potential bug in the program: it performed an invalid operation, but the Stacked Borrows rules it violated are still experimental
+   = help: see https://github.com/rust-lang/unsafe-code-guidelines/blob/master/wip/stacked-borrows.md for further information
+help: <4021> was created by a SharedReadOnly retag at offsets [0x0..0x18]
+  --> src/polygon.rs:86:22
+   |
+86 |         let points = &self.points as *const _ as *mut Vec<Point>;
+   |                      ^^^^^^^^^^^^
+   = note: this is on thread `polygon::test::`
+
+error: aborting due to 1 previous error
+
+error: test failed, to rerun pass `--lib`
+   Compiling shapes v0.3.1 (/home/someone/shapes)
+error: Undefined Behavior: constructing invalid value of type &alloc::string::String: encountered a dangling reference (use-after-free)
+  --> src/label.rs:12:5
+   |
+   = help: this indicates a bug in the program: it performed an invalid operation, and caused Undefined Behavior
+
+error: aborting due to 1 previous error
+";
+
+    #[test]
+    fn ignores_program_specifics() {
+        let diagnostics = diagnostics(LOG, Path::new("/home/someone/shapes"));
+        assert_eq!(diagnostics.len(), 2);
+
+        let signature = Signature::new(&diagnostics[0]);
+        assert_eq!(signature.kind, UbKind::Aliasing);
+        assert!(signature.words.contains("sharedreadonly"));
+        assert!(!signature.words.iter().any(|word| word.contains("alloc")));
+        assert!(signature
+            .notes
+            .contains("<TAG> was created by a SharedReadOnly retag"));
+
+        let signature = Signature::new(&diagnostics[1]);
+        assert_eq!(signature.kind, UbKind::InvalidValue);
+        assert!(!signature.words.contains("string"));
+    }
+
+    #[test]
+    fn finds_the_matching_examples() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let rules = rules(&zoo).unwrap();
+        let diagnostics = diagnostics(LOG, Path::new("/home/someone/shapes"));
+
+        let best = &classify(&diagnostics[0], &rules)[0];
+        assert_eq!(
+            best.rule.test,
+            "mutate_immutable::test::mutate_via_shared_reference"
+        );
+
+        let matches = classify(&diagnostics[1], &rules);
+        let best: Vec<&str> = matches[..2].iter().map(|m| m.rule.test.as_str()).collect();
+        assert_eq!(
+            best,
+            [
+                "bad_lifetime::test::bad_lifetime",
+                "buggy_drain::fails_when_drain_leaked"
+            ]
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! What the compiler makes of each example: the LLVM IR rustc hands to the
+//! optimizer, and the assembly that comes out.
+//!
+//! Most of the UB in the zoo breaks a promise rustc makes to LLVM, like
+//! `noalias` on a `&mut` argument or the `!range` of values a load can
+//! produce. Those promises are only visible in the IR before LLVM's own
+//! passes run, so the IR is taken with `-C no-prepopulate-passes`; the
+//! assembly comes from a normal optimized build.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::example::Example;
+use crate::Result;
+
+/// The optimization level the code is generated at; rustc only passes most
+/// of these annotations along when optimizing.
+pub const OPT_LEVEL: u8 = 2;
+
+/// One of the example's own functions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    /// The path within the example, e.g. `test::update_two_ints`.
+    pub name: String,
+    /// The function's IR or assembly.
+    pub text: String,
+    /// The annotations the optimizer may rely on, e.g.
+    /// `%a: noalias dereferenceable(4)`. Always empty for assembly.
+    pub facts: Vec<String>,
+}
+
+/// How to generate an example's IR and assembly.
+#[derive(Debug, Clone)]
+pub struct Codegen {
+    /// The rustup toolchain to build with.
+    pub toolchain: String,
+}
+
+impl Codegen {
+    pub fn new(toolchain: impl Into<String>) -> Codegen {
+        Codegen {
+            toolchain: toolchain.into(),
+        }
+    }
+
+    /// The `cargo rustc` command that writes the example's test crate as
+    /// `<package>.ll` (and `<package>.s`) to `out`. With `annotated`, LLVM's
+    /// passes are skipped and only the IR is written.
+    pub fn command(&self, root: &Path, example: &Example, out: &Path, annotated: bool) -> Command {
+        let file = |extension: &str| out.join(format!("{}.{extension}", example.package));
+        let mut emit = format!("link,llvm-ir={}", file("ll").display());
+        if !annotated {
+            emit.push_str(&format!(",asm={}", file("s").display()));
+        }
+
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(root)
+            .env_remove("RUSTUP_TOOLCHAIN")
+            .arg(format!("+{}", self.toolchain))
+            .args(["rustc", "--quiet", "--lib", "--profile", "test"])
+            .args(["--package", &example.package])
+            .arg("--target-dir")
+            .arg(out)
+            .arg("--")
+            .arg(format!("-Copt-level={OPT_LEVEL}"))
+            .args(["-Ccodegen-units=1", "-Cdebuginfo=0"])
+            .arg(format!("--emit={emit}"));
+        if annotated {
+            command.arg("-Cno-prepopulate-passes");
+        }
+        command
+    }
+
+    /// The IR rustc generates for the example's functions, before LLVM
+    /// optimizes it.
+    pub fn ir(&self, root: &Path, example: &Example) -> Result<Vec<Function>> {
+        let ir = self.build(root, example, true, "ll")?;
+        Ok(parse_ir(&ir, &crate_name(example)))
+    }
+
+    /// The optimized assembly for the example's functions. Functions that
+    /// were inlined everywhere have none.
+    pub fn asm(&self, root: &Path, example: &Example) -> Result<Vec<Function>> {
+        let asm = self.build(root, example, false, "s")?;
+        Ok(parse_asm(&asm, &crate_name(example)))
+    }
+
+    fn build(
+        &self,
+        root: &Path,
+        example: &Example,
+        annotated: bool,
+        extension: &str,
+    ) -> Result<String> {
+        let mode = if annotated { "annotated" } else { "optimized" };
+        let out = root.join("target/codegen").join(mode);
+        let path = out.join(format!("{}.{extension}", example.package));
+        // Cargo won't rebuild (and so rustc won't write anything) if nothing
+        // changed, so make it forget it built the example.
+        let _ = std::fs::remove_file(&path);
+        let fingerprints = out.join("debug/.fingerprint");
+        for entry in std::fs::read_dir(&fingerprints).into_iter().flatten() {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name
+                .to_string_lossy()
+                .starts_with(&format!("{}-", crate_name(example)))
+            {
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        let output = self
+            .command(root, example, &out, annotated)
+            .output()
+            .map_err(|e| format!("running cargo rustc: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "building {} failed:\n{}",
+                example.name,
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into());
+        }
+        std::fs::read_to_string(&path)
+            .map_err(|e| format!("reading {}: {e}", path.display()).into())
+    }
+}
+
+/// The example's crate name, as it appears in symbols.
+fn crate_name(example: &Example) -> String {
+    example.package.replace('-', "_")
+}
+
+/// The example's own function a symbol belongs to, without the crate name;
+/// `None` for anything else, including closures and the test harness's
+/// `main`.
+fn own_function(symbol: &str, krate: &str) -> Option<String> {
+    let name = format!("{:#}", rustc_demangle::demangle(symbol));
+    let name = name.strip_prefix(krate)?.strip_prefix("::")?;
+    if name == "main" || name.contains("{{closure}}") {
+        return None;
+    }
+    Some(name.to_owned())
+}
+
+/// Split LLVM IR into the example's functions, and pick out the
+/// annotations on each.
+pub fn parse_ir(ir: &str, krate: &str) -> Vec<Function> {
+    static METADATA: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^(![0-9]+) = !\{(.*)\}$").unwrap());
+    static SYMBOL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r#"@("[^"]+"|[\w.$]+)\("#).unwrap());
+
+    let metadata: BTreeMap<&str, &str> = ir
+        .lines()
+        .filter_map(|line| {
+            let captures = METADATA.captures(line)?;
+            Some((captures.get(1)?.as_str(), captures.get(2)?.as_str()))
+        })
+        .collect();
+
+    let mut functions = Vec::new();
+    let mut lines = ir.lines();
+    while let Some(line) = lines.next() {
+        if !line.starts_with("define ") {
+            continue;
+        }
+        let body: Vec<&str> = lines.by_ref().take_while(|line| *line != "}").collect();
+        let Some(captures) = SYMBOL.captures(line) else {
+            continue;
+        };
+        let symbol = captures[1].trim_matches('"');
+        let Some(name) = own_function(symbol, krate) else {
+            continue;
+        };
+
+        let start = captures.get(0).unwrap().end();
+        let mut facts = parameter_facts(&line[start..]);
+        for line in &body {
+            for fact in instruction_facts(line, &metadata) {
+                if !facts.contains(&fact) {
+                    facts.push(fact);
+                }
+            }
+        }
+        let mut text = vec![line];
+        text.extend(body);
+        text.push("}");
+        functions.push(Function {
+            name,
+            text: text.join("\n"),
+            facts,
+        });
+    }
+    functions
+}
+
+/// The attributes on a function's parameters that make promises about
+/// them. `params` starts just after the opening parenthesis.
+fn parameter_facts(params: &str) -> Vec<String> {
+    static ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^(noalias|nonnull|readonly|dereferenceable\(\d+\)|range\(.*\))$").unwrap()
+    });
+
+    // Attributes have parentheses of their own, so split by hand.
+    let mut depth = 0;
+    let mut list = vec![String::new()];
+    for c in params.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => break,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                list.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        list.last_mut().unwrap().push(c);
+    }
+    list.iter()
+        .filter_map(|param| {
+            let words = split_words(param);
+            let (name, attributes) = words.split_last()?;
+            // rustc names the return slot and unnamed arguments `%_N`.
+            if !name.starts_with('%') || name.starts_with("%_") {
+                return None;
+            }
+            let attributes: Vec<&str> = attributes
+                .iter()
+                .copied()
+                .filter(|word| ATTRIBUTE.is_match(word))
+                .collect();
+            (!attributes.is_empty()).then(|| format!("{name}: {}", attributes.join(" ")))
+        })
+        .collect()
+}
+
+/// Split on spaces that aren't inside parentheses.
+fn split_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ' ' if depth == 0 => {
+                if start < i {
+                    words.push(&text[start..i]);
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// The promises a load makes about the value it produces. (`unreachable`
+/// and `llvm.assume` are left out: nearly all of them come from panics and
+/// the standard library, not from the example.)
+fn instruction_facts(line: &str, metadata: &BTreeMap<&str, &str>) -> Vec<String> {
+    static LOAD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"= load (\S+), ").unwrap());
+    static RANGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!range (![0-9]+)").unwrap());
+    static BOUNDS: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"^\w+ (-?\d+), \w+ (-?\d+)$").unwrap());
+
+    let line = line.trim();
+    let mut facts = Vec::new();
+    if let Some(load) = LOAD.captures(line) {
+        let ty = &load[1];
+        if let Some(range) = RANGE.captures(line) {
+            if let Some(bounds) = metadata
+                .get(&range[1])
+                .and_then(|node| BOUNDS.captures(node))
+            {
+                facts.push(format!(
+                    "load {ty} in range [{}, {})",
+                    &bounds[1], &bounds[2]
+                ));
+            }
+        }
+        if line.contains("!nonnull") {
+            facts.push(format!("load {ty} !nonnull"));
+        }
+    }
+    facts
+}
+
+/// Split assembly into the example's functions.
+pub fn parse_asm(asm: &str, krate: &str) -> Vec<Function> {
+    let mut functions = Vec::new();
+    let mut lines = asm.lines();
+    while let Some(line) = lines.next() {
+        let Some(symbol) = line.strip_suffix(':') else {
+            continue;
+        };
+        let symbol = symbol.trim_matches('"');
+        let Some(name) = own_function(symbol, krate) else {
+            continue;
+        };
+        let body: Vec<&str> = lines
+            .by_ref()
+            .take_while(|line| !line.starts_with(".Lfunc_end"))
+            .filter(|line| !line.trim_start().starts_with(".cfi_"))
+            .collect();
+        let mut text = vec![line];
+        text.extend(body);
+        functions.push(Function {
+            name,
+            text: text.join("\n"),
+            facts: Vec::new(),
+        });
+    }
+    functions
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const IR: &str = r#"
+define internal void @_ZN16illegal_aliasing4test15update_two_ints17h4a396e61c4e877baE(ptr noalias noundef align 4 dereferenceable(4) %a, ptr noalias noundef align 4 dereferenceable(4) %b) unnamed_addr #2 {
+start:
+  %_3 = load i32, ptr %b, align 4, !noundef !4
+  ret void
+}
+define internal void @"_ZN16illegal_aliasing4test12atomic_alias28_$u7b$$u7b$closure$u7d$$u7d$17hd7b2ca64f8b4e7a0E"(ptr noalias noundef nonnull readonly align 1 %_1) unnamed_addr #0 {
+start:
+  ret void
+}
+define internal void @_ZN23enum_bogus_discriminant24enum_bogus_discriminant217h7bb8e5efae4188bdE() unnamed_addr #2 {
+start:
+  %_z = load i8, ptr %y, align 1, !range !6, !noundef !4
+  unreachable
+}
+!4 = !{}
+!6 = !{i8 0, i8 3}
+"#;
+
+    #[test]
+    fn finds_annotations() {
+        let functions = parse_ir(IR, "illegal_aliasing");
+        assert_eq!(functions.len(), 1);
+        assert_eq!(functions[0].name, "test::update_two_ints");
+        assert_eq!(
+            functions[0].facts,
+            [
+                "%a: noalias dereferenceable(4)",
+                "%b: noalias dereferenceable(4)"
+            ]
+        );
+
+        let functions = parse_ir(IR, "enum_bogus_discriminant");
+        assert_eq!(functions[0].name, "enum_bogus_discriminant2");
+        assert_eq!(functions[0].facts, ["load i8 in range [0, 3)"]);
+    }
+
+    #[test]
+    fn splits_assembly() {
+        let asm = "\
+_ZN16illegal_aliasing4test15update_two_ints17h4a396e61c4e877baE:
+\t.cfi_startproc
+\tmovl\t(%rsi), %eax
+\tretq
+.Lfunc_end0:
+main:
+\tretq
+";
+        let functions = parse_asm(asm, "illegal_aliasing");
+        assert_eq!(functions.len(), 1);
+        assert_eq!(
+            functions[0].text,
+            "_ZN16illegal_aliasing4test15update_two_ints17h4a396e61c4e877baE:\n\tmovl\t(%rsi), %eax\n\tretq"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Finding the examples under `unsound_examples/`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::metadata::Metadata;
+use crate::source::{SourceFile, TestFn};
+use crate::Result;
+
+/// The directory, relative to the workspace root, holding the examples.
+pub const EXAMPLES_DIR: &str = "unsound_examples";
+
+/// Where the zoo is published; links in generated documents point here.
+pub const REPOSITORY: &str = "https://github.com/ericseppanen/unsound_zoo";
+
+/// One example crate.
+#[derive(Debug, Clone)]
+pub struct Example {
+    /// The directory name, e.g. `invalid_bool`.
+    pub name: String,
+    /// The Cargo package name, which should be the same as the directory.
+    pub package: String,
+    /// The crate directory.
+    pub dir: PathBuf,
+    /// The `[package.metadata.zoo]` section, if there is one.
+    pub metadata: Option<Metadata>,
+    pub source: SourceFile,
+}
+
+#[derive(Deserialize)]
+struct Manifest {
+    package: Package,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+    #[serde(default)]
+    metadata: PackageMetadata,
+}
+
+#[derive(Default, Deserialize)]
+struct PackageMetadata {
+    zoo: Option<Metadata>,
+}
+
+impl Example {
+    pub fn load(dir: &Path) -> Result<Example> {
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| format!("bad example directory {}", dir.display()))?
+            .to_owned();
+
+        let manifest_path = dir.join("Cargo.toml");
+        let manifest = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("reading {}: {e}", manifest_path.display()))?;
+        let manifest: Manifest = toml::from_str(&manifest)
+            .map_err(|e| format!("parsing {}: {e}", manifest_path.display()))?;
+
+        let source = SourceFile::read(&dir.join("src/lib.rs"))?;
+
+        Ok(Example {
+            name,
+            package: manifest.package.name,
+            dir: dir.to_owned(),
+            metadata: manifest.package.metadata.zoo,
+            source,
+        })
+    }
+
+    /// The title from the metadata, falling back to the directory name.
+    pub fn title(&self) -> &str {
+        self.metadata
+            .as_ref()
+            .map_or(&self.name, |metadata| &metadata.title)
+    }
+
+    /// A link to the example's source on GitHub.
+    pub fn source_url(&self) -> String {
+        format!(
+            "{REPOSITORY}/blob/main/{EXAMPLES_DIR}/{}/src/lib.rs",
+            self.name
+        )
+    }
+}
+
+/// The file, at the workspace root, mapping old names to IDs.
+pub const REDIRECTS: &str = "redirects.toml";
+
+/// All of the examples in the workspace.
+#[derive(Debug, Clone)]
+pub struct Zoo {
+    pub root: PathBuf,
+    pub examples: Vec<Example>,
+    /// Names examples and tests used to go by, and the IDs they have now.
+    pub redirects: BTreeMap<String, String>,
+}
+
+#[derive(Deserialize)]
+struct Redirects {
+    redirects: BTreeMap<String, String>,
+}
+
+impl Zoo {
+    /// Load the zoo that contains the current directory, or failing that,
+    /// the one this binary was built from.
+    pub fn discover() -> Result<Zoo> {
+        let cwd = std::env::current_dir()?;
+        let root = cwd
+            .ancestors()
+            .find(|dir| dir.join(EXAMPLES_DIR).is_dir() && dir.join("Cargo.toml").is_file())
+            .unwrap_or_else(|| {
+                // The zoo binary lives one level below the workspace root.
+                Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap()
+            });
+        Zoo::load(root)
+    }
+
+    pub fn load(root: &Path) -> Result<Zoo> {
+        let examples_dir = root.join(EXAMPLES_DIR);
+        let mut dirs = Vec::new();
+        for entry in std::fs::read_dir(&examples_dir)
+            .map_err(|e| format!("reading {}: {e}", examples_dir.display()))?
+        {
+            let path = entry?.path();
+            if path.join("Cargo.toml").is_file() {
+                dirs.push(path);
+            }
+        }
+        dirs.sort();
+
+        let examples = dirs
+            .iter()
+            .map(|dir| Example::load(dir))
+            .collect::<Result<_>>()?;
+
+        let redirects_path = root.join(REDIRECTS);
+        let redirects = match std::fs::read_to_string(&redirects_path) {
+            Ok(text) => {
+                toml::from_str::<Redirects>(&text)
+                    .map_err(|e| format!("parsing {}: {e}", redirects_path.display()))?
+                    .redirects
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(format!("reading {}: {e}", redirects_path.display()).into()),
+        };
+
+        Ok(Zoo {
+            root: root.to_owned(),
+            examples,
+            redirects,
+        })
+    }
+
+    /// Find an example by directory or package name, by ID, or by a name it
+    /// used to have.
+    pub fn example(&self, name: &str) -> Option<&Example> {
+        let name = self.redirects.get(name).map_or(name, String::as_str);
+        self.examples.iter().find(|example| {
+            example.name == name
+                || example.package == name
+                || example
+                    .metadata
+                    .as_ref()
+                    .is_some_and(|metadata| metadata.id == name)
+        })
+    }
+
+    /// Resolve `example`, `example::test`, or an example or test ID (like
+    /// `UZ-0007` or `UZ-0007.2`) into an example and an optional test.
+    pub fn resolve(&self, spec: &str) -> Result<(&Example, Option<&TestFn>)> {
+        let spec = self.redirects.get(spec).map_or(spec, String::as_str);
+        if let Some((example_id, _)) = spec.split_once('.').filter(|_| spec.starts_with("UZ-")) {
+            let example = self
+                .example(example_id)
+                .ok_or_else(|| format!("no example with ID `{example_id}`"))?;
+            let test = example.metadata.as_ref().and_then(|metadata| {
+                let (path, _) = metadata.test_ids.iter().find(|(_, id)| *id == spec)?;
+                example.source.test(path)
+            });
+            let test = test.ok_or_else(|| format!("no test with ID `{spec}`"))?;
+            return Ok((example, Some(test)));
+        }
+
+        let (example_name, test_name) = match spec.split_once("::") {
+            Some((example, test)) => (example, Some(test)),
+            None => (spec, None),
+        };
+        let example = self
+            .example(example_name)
+            .ok_or_else(|| format!("no example named `{example_name}`"))?;
+        let test = test_name
+            .map(|name| {
+                example
+                    .source
+                    .test(name)
+                    .ok_or_else(|| format!("`{}` has no test named `{name}`", example.name))
+            })
+            .transpose()?;
+        Ok((example, test))
+    }
+
+    /// A path relative to the workspace root, for display.
+    pub fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+}
@@ -0,0 +1,473 @@
+//! `cargo zoo explore`: a terminal UI for browsing the examples by category,
+//! reading each test with its comments, and running it under Miri without
+//! leaving the source.
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::example::{Example, Zoo};
+use crate::metadata::Category;
+use crate::miri::{Miri, Outcome};
+use crate::report::{expectation_label, outcome_label};
+use crate::source::TestFn;
+use crate::Result;
+
+/// How many lines PgUp and PgDn scroll the source by.
+const PAGE: isize = 10;
+
+/// Run the explorer until the user quits, restoring the terminal even if
+/// drawing fails.
+pub fn run(zoo: &Zoo, miri: Miri) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let result = Explorer::new(zoo, miri).run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// A line in the list of tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Row {
+    /// A category or an example, which can't be selected.
+    Heading(String),
+    /// Indexes into `Zoo::examples` and the example's tests.
+    Test(usize, usize),
+}
+
+/// The list of tests, grouped under their category and example in the
+/// README's order. Examples without metadata come last.
+pub fn rows(zoo: &Zoo) -> Vec<Row> {
+    let mut groups: Vec<(String, Vec<usize>)> = Category::ALL
+        .iter()
+        .map(|category| {
+            let mut examples: Vec<(u32, usize)> = zoo
+                .examples
+                .iter()
+                .enumerate()
+                .filter_map(|(index, example)| {
+                    let metadata = example.metadata.as_ref()?;
+                    (metadata.category == *category).then_some((metadata.order, index))
+                })
+                .collect();
+            examples.sort();
+            let examples = examples.into_iter().map(|(_, index)| index).collect();
+            (category.heading().to_owned(), examples)
+        })
+        .collect();
+    let uncategorized = (0..zoo.examples.len())
+        .filter(|&index| zoo.examples[index].metadata.is_none())
+        .collect();
+    groups.push(("Uncategorized".to_owned(), uncategorized));
+
+    let mut rows = Vec::new();
+    for (heading, examples) in groups {
+        if examples.is_empty() {
+            continue;
+        }
+        rows.push(Row::Heading(heading));
+        for index in examples {
+            let example = &zoo.examples[index];
+            rows.push(Row::Heading(format!("  {}", example.title())));
+            rows.extend((0..example.source.tests.len()).map(|test| Row::Test(index, test)));
+        }
+    }
+    rows
+}
+
+/// The example's source with line numbers, the test's lines marked, its
+/// comments set apart from the code, and Miri's diagnostic, if there is
+/// one, right under the line it points at (or the end of the test). Also
+/// returns the index of the line the test's excerpt starts on.
+pub fn source_view(
+    example: &Example,
+    test: &TestFn,
+    outcome: Option<&Outcome>,
+) -> (Vec<Line<'static>>, usize) {
+    let source = &example.source;
+    let diagnostic = match outcome {
+        Some(Outcome::Ub(diagnostic)) => {
+            let line = diagnostic_line(example, &diagnostic.text).unwrap_or(*test.lines.end());
+            Some((line, diagnostic.text.as_str()))
+        }
+        _ => None,
+    };
+
+    let start = *source.excerpt(test).start();
+    let mut lines = Vec::new();
+    let mut top = 0;
+    for (index, text) in source.text.lines().enumerate() {
+        let number = index + 1;
+        if number == start {
+            top = lines.len();
+        }
+        let gutter = if test.lines.contains(&number) {
+            Span::styled(format!("{number:>4} ▌ "), Style::new().fg(Color::Yellow))
+        } else {
+            Span::styled(format!("{number:>4}   "), Style::new().dark_gray())
+        };
+        let mut spans = vec![gutter];
+        let (code, comment) = split_comment(text);
+        if !code.is_empty() {
+            spans.push(Span::raw(code.to_owned()));
+        }
+        if !comment.is_empty() {
+            spans.push(Span::styled(comment.to_owned(), comment_style()));
+        }
+        lines.push(Line::from(spans));
+
+        if let Some((_, text)) = diagnostic.filter(|(line, _)| *line == number) {
+            let style = Style::new().fg(Color::Red);
+            lines.extend(
+                text.lines()
+                    .map(|line| Line::styled(format!("       {line}"), style)),
+            );
+        }
+    }
+    (lines, top)
+}
+
+/// The line of the example's source a diagnostic points at: where the
+/// undefined behavior happened if that's in the example, or else the
+/// example's innermost frame in the backtrace, for UB found inside the
+/// standard library.
+fn diagnostic_line(example: &Example, text: &str) -> Option<usize> {
+    let source = format!("/{}/src/lib.rs:", example.name);
+    text.lines().find_map(|line| {
+        let line = line.trim_start();
+        let location = line
+            .strip_prefix("--> ")
+            .or_else(|| line.strip_prefix("at "))?;
+        let (_, rest) = location.split_once(&source)?;
+        rest.split(':').next()?.parse().ok()
+    })
+}
+
+/// A line's code and its trailing `//` comment, which is the whole line if
+/// the line is only a comment. A `//` inside a string literal doesn't count.
+fn split_comment(line: &str) -> (&str, &str) {
+    let mut in_string = false;
+    let mut chars = line.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\\' if in_string => {
+                chars.next();
+            }
+            '"' => in_string = !in_string,
+            '/' if !in_string && line[index + 1..].starts_with('/') => {
+                return line.split_at(index);
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn comment_style() -> Style {
+    Style::new().fg(Color::Green).add_modifier(Modifier::ITALIC)
+}
+
+/// Which test, in which variant of its example.
+type Key = (usize, usize, bool);
+
+struct Explorer<'a> {
+    zoo: &'a Zoo,
+    miri: Miri,
+    rows: Vec<Row>,
+    list: ListState,
+    /// Lines scrolled past the start of the test's excerpt.
+    scroll: isize,
+    /// Whether to run the tests with the example's `sound` feature.
+    sound: bool,
+    outcomes: BTreeMap<Key, Outcome>,
+    /// The test Miri is running, and where its outcome will arrive.
+    running: Option<(Key, Receiver<Result<Outcome>>)>,
+    /// The last run that failed to start, or couldn't be made sense of.
+    error: Option<String>,
+}
+
+impl<'a> Explorer<'a> {
+    fn new(zoo: &'a Zoo, miri: Miri) -> Explorer<'a> {
+        let rows = rows(zoo);
+        let first = rows.iter().position(|row| matches!(row, Row::Test(..)));
+        Explorer {
+            zoo,
+            miri,
+            rows,
+            list: ListState::default().with_selected(first),
+            scroll: 0,
+            sound: false,
+            outcomes: BTreeMap::new(),
+            running: None,
+            error: None,
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            self.receive();
+            terminal.draw(|frame| self.draw(frame))?;
+            // Wake up now and then to pick up Miri's result.
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                KeyCode::PageDown => self.scroll += PAGE,
+                KeyCode::PageUp => self.scroll -= PAGE,
+                KeyCode::Char('r') => self.start(),
+                KeyCode::Char('s') => self.sound = !self.sound,
+                _ => {}
+            }
+        }
+    }
+
+    /// The selected test, as indexes into `Zoo::examples` and its tests.
+    fn selected(&self) -> Option<(usize, usize)> {
+        match self.rows.get(self.list.selected()?)? {
+            Row::Test(example, test) => Some((*example, *test)),
+            Row::Heading(_) => None,
+        }
+    }
+
+    /// Move to the next or previous test, skipping headings.
+    fn select(&mut self, step: isize) {
+        let Some(mut index) = self.list.selected() else {
+            return;
+        };
+        loop {
+            match index.checked_add_signed(step) {
+                Some(next) if next < self.rows.len() => index = next,
+                _ => return,
+            }
+            if let Row::Test(..) = self.rows[index] {
+                self.list.select(Some(index));
+                self.scroll = 0;
+                return;
+            }
+        }
+    }
+
+    /// Run the selected test under Miri in the background, unless a run is
+    /// already going.
+    fn start(&mut self) {
+        let Some((example, test)) = self.selected() else {
+            return;
+        };
+        if self.running.is_some() {
+            return;
+        }
+        let miri = if self.sound {
+            self.miri.sound()
+        } else {
+            self.miri.clone()
+        };
+        let root = self.zoo.root.clone();
+        let (sender, receiver) = mpsc::channel();
+        let owned = self.zoo.examples[example].clone();
+        thread::spawn(move || {
+            let test = &owned.source.tests[test];
+            let outcome = miri
+                .run(&root, &owned, Some(test))
+                .map(|mut run| run.outcomes.remove(0).1);
+            // The explorer may have quit in the meantime.
+            let _ = sender.send(outcome);
+        });
+        self.error = None;
+        self.running = Some(((example, test, self.sound), receiver));
+    }
+
+    /// Pick up the outcome of the run in the background, if it's done.
+    fn receive(&mut self) {
+        let Some((key, receiver)) = &self.running else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(Ok(outcome)) => {
+                self.outcomes.insert(*key, outcome);
+            }
+            Ok(Err(error)) => self.error = Some(error.to_string()),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                self.error = Some("the thread running Miri panicked".to_owned());
+            }
+        }
+        self.running = None;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list, source] =
+            Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)])
+                .areas(main);
+
+        let items: Vec<ListItem> = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Heading(heading) if !heading.starts_with(' ') => {
+                    ListItem::new(heading.clone().bold().underlined())
+                }
+                Row::Heading(heading) => ListItem::new(heading.clone().bold()),
+                Row::Test(example, test) => {
+                    let path = &self.zoo.examples[*example].source.tests[*test].path;
+                    let mut spans = vec![Span::raw(format!("    {path}"))];
+                    if let Some(outcome) = self.outcomes.get(&(*example, *test, self.sound)) {
+                        spans.push(Span::styled(
+                            format!("  {}", outcome_label(outcome)),
+                            outcome_style(outcome),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans))
+                }
+            })
+            .collect();
+        let variant = if self.sound { "sound" } else { "unsound" };
+        let tests = List::new(items)
+            .block(Block::bordered().title(format!(" Examples ({variant}) ")))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(tests, list, &mut self.list);
+
+        let Some((index, test)) = self.selected() else {
+            frame.render_widget(Block::bordered(), source);
+            return;
+        };
+        let outcome = self.outcomes.get(&(index, test, self.sound));
+        let example = &self.zoo.examples[index];
+        let test = &example.source.tests[test];
+        let (lines, top) = source_view(example, test, outcome);
+        let scroll = top.saturating_add_signed(self.scroll).min(lines.len());
+        self.scroll = scroll as isize - top as isize;
+        let title = format!(
+            " {}/src/lib.rs · {} · expects {} ",
+            example.name,
+            test.path,
+            expectation_label(test.expectation.as_ref())
+        );
+        let code = Paragraph::new(lines)
+            .block(Block::bordered().title(title))
+            .scroll((u16::try_from(scroll).unwrap_or(u16::MAX), 0));
+        frame.render_widget(code, source);
+
+        let state = if let Some(((example, test, _), _)) = &self.running {
+            let example = &self.zoo.examples[*example];
+            format!(
+                "running {}::{} under Miri…",
+                example.name, example.source.tests[*test].path
+            )
+        } else if let Some(error) = &self.error {
+            format!("error: {error}")
+        } else {
+            match outcome {
+                Some(Outcome::Error(error)) => format!("Miri says: {error}"),
+                Some(outcome) => format!("Miri says: {}", outcome_label(outcome)),
+                None => String::new(),
+            }
+        };
+        let help = format!(
+            " ↑↓ select · PgUp/PgDn scroll · r run under Miri · s {} · q quit  {state}",
+            if self.sound { "unsound" } else { "sound" }
+        );
+        frame.render_widget(Line::from(help).reversed(), status);
+    }
+}
+
+fn outcome_style(outcome: &Outcome) -> Style {
+    match outcome {
+        Outcome::Ub(_) | Outcome::Error(_) => Style::new().fg(Color::Red),
+        Outcome::Clean => Style::new().fg(Color::Green),
+        Outcome::Ignored | Outcome::NotRun => Style::new().dark_gray(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::miri::parse_diagnostic;
+    use crate::snapshot;
+
+    use super::*;
+
+    fn zoo() -> Zoo {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        Zoo::load(root).unwrap()
+    }
+
+    #[test]
+    fn lists_tests_by_category() {
+        let zoo = zoo();
+        let rows = rows(&zoo);
+        assert_eq!(rows[0], Row::Heading(Category::Simple.heading().to_owned()));
+        let invalid_bool = zoo.example("invalid_bool").unwrap();
+        assert_eq!(rows[1], Row::Heading(format!("  {}", invalid_bool.title())));
+        let complex = Row::Heading(Category::Complex.heading().to_owned());
+        assert!(rows.contains(&complex));
+
+        let tests = rows
+            .iter()
+            .filter(|row| matches!(row, Row::Test(..)))
+            .count();
+        let total: usize = zoo.examples.iter().map(|e| e.source.tests.len()).sum();
+        assert_eq!(tests, total);
+    }
+
+    #[test]
+    fn splits_comments() {
+        assert_eq!(split_comment("    // Why."), ("    ", "// Why."));
+        assert_eq!(
+            split_comment("let x = 0; // Why."),
+            ("let x = 0; ", "// Why.")
+        );
+        assert_eq!(
+            split_comment(r#"let url = "a\"//b";"#),
+            (r#"let url = "a\"//b";"#, "")
+        );
+    }
+
+    #[test]
+    fn shows_diagnostic_under_its_line() {
+        let zoo = zoo();
+        let example = zoo.example("null_reference").unwrap();
+        let test = &example.source.tests[0];
+        let snapshot = std::fs::read_to_string(snapshot::path(example, test)).unwrap();
+        let outcome = Outcome::Ub(parse_diagnostic(&snapshot).unwrap());
+
+        let (lines, _) = source_view(example, test, Some(&outcome));
+        let text: Vec<String> = lines.iter().map(ToString::to_string).collect();
+        let error = text
+            .iter()
+            .position(|line| line.trim_start().starts_with("error: Undefined Behavior"))
+            .unwrap();
+        assert!(text[error - 1].starts_with("  12 ▌"), "{}", text[error - 1]);
+        assert!(text[error + 1..]
+            .iter()
+            .any(|line| line.starts_with("  13 ")));
+    }
+
+    #[test]
+    fn finds_the_line_behind_ub_in_std() {
+        let zoo = zoo();
+        let example = zoo.example("bad_lifetime").unwrap();
+        let test = &example.source.tests[0];
+        let snapshot = std::fs::read_to_string(snapshot::path(example, test)).unwrap();
+        assert_eq!(diagnostic_line(example, &snapshot), Some(24));
+        assert_eq!(diagnostic_line(example, "error: Undefined Behavior"), None);
+    }
+}
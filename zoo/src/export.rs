@@ -0,0 +1,373 @@
+//! Writing `cargo zoo run`'s results in formats other tools understand:
+//! JUnit XML for test reports, and SARIF for code-scanning viewers.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use serde_json::{json, Value};
+use zoo_harness::UbKind;
+
+use crate::example::{Example, EXAMPLES_DIR, REPOSITORY};
+use crate::miri::{Outcome, Span};
+use crate::report::{expectation_label, Verdict};
+use crate::source::TestFn;
+use crate::Result;
+
+/// One test's result from `cargo zoo run`.
+#[derive(Debug, Clone)]
+pub struct TestResult<'a> {
+    pub example: &'a Example,
+    pub test: TestFn,
+    pub outcome: Outcome,
+    pub verdict: Verdict,
+}
+
+impl TestResult<'_> {
+    fn expected(&self) -> String {
+        self.test
+            .expectation
+            .as_ref()
+            .map_or("nothing declared".to_owned(), ToString::to_string)
+    }
+
+    fn actual(&self) -> String {
+        match &self.outcome {
+            Outcome::Ub(diagnostic) => format!("UB: {}", diagnostic.message),
+            Outcome::Error(error) => format!("error: {error}"),
+            Outcome::Clean => "clean".to_owned(),
+            Outcome::Ignored => "ignored".to_owned(),
+            Outcome::NotRun => "not run".to_owned(),
+        }
+    }
+}
+
+/// A JUnit XML report: a test suite per example, and a test case per test,
+/// with the expected and actual outcomes as properties. Regressions are
+/// failures; tests that didn't run are skipped.
+pub fn junit(results: &[TestResult]) -> String {
+    let mut suites: Vec<(&Example, Vec<&TestResult>)> = Vec::new();
+    for result in results {
+        match suites.last_mut() {
+            Some((example, tests)) if example.name == result.example.name => tests.push(result),
+            _ => suites.push((result.example, vec![result])),
+        }
+    }
+
+    let count = |tests: &[&TestResult], verdict: Verdict| {
+        tests
+            .iter()
+            .filter(|result| result.verdict == verdict)
+            .count()
+    };
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let all: Vec<&TestResult> = results.iter().collect();
+    // Writing to a `String` can't fail.
+    let _ = writeln!(
+        out,
+        "<testsuites name=\"unsound-zoo\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+        all.len(),
+        count(&all, Verdict::Regression),
+        count(&all, Verdict::Skipped),
+    );
+    for (example, tests) in &suites {
+        let _ = writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            escape(&example.name),
+            tests.len(),
+            count(tests, Verdict::Regression),
+            count(tests, Verdict::Skipped),
+        );
+        for result in tests {
+            let source = format!("{EXAMPLES_DIR}/{}/src/lib.rs", example.name);
+            let _ = writeln!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\">",
+                escape(&result.test.path),
+                escape(&example.name),
+                escape(&source),
+                result.test.lines.start(),
+            );
+            out.push_str("      <properties>\n");
+            for (name, value) in [
+                ("expected", result.expected()),
+                ("actual", result.actual()),
+                ("verdict", result.verdict.to_string()),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "        <property name=\"{name}\" value=\"{}\"/>",
+                    escape(&value)
+                );
+            }
+            out.push_str("      </properties>\n");
+            match result.verdict {
+                Verdict::Regression => {
+                    let _ = writeln!(
+                        out,
+                        "      <failure message=\"expected {}, got {}\"/>",
+                        escape(&expectation_label(result.test.expectation.as_ref())),
+                        escape(&result.actual()),
+                    );
+                }
+                Verdict::Skipped => {
+                    let _ = writeln!(
+                        out,
+                        "      <skipped message=\"{}\"/>",
+                        escape(&result.actual())
+                    );
+                }
+                Verdict::Pass | Verdict::Undeclared => {}
+            }
+            if let Outcome::Ub(diagnostic) = &result.outcome {
+                let _ = writeln!(
+                    out,
+                    "      <system-err>{}</system-err>",
+                    escape(&diagnostic.text)
+                );
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            c if c.is_control() && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// A SARIF 2.1.0 log with a result for every UB diagnostic, located where
+/// Miri said it happened, with Miri's notes (like where a borrow tag was
+/// created) as related locations. Rules are the kinds of UB. UB a test
+/// declared with `#[expect_ub]` is reported as suppressed in source, so
+/// viewers show only the surprises by default.
+pub fn sarif(root: &Path, results: &[TestResult]) -> Result<String> {
+    let rules: Vec<Value> = UbKind::ALL
+        .iter()
+        .map(|kind| {
+            json!({
+                "id": kind.name(),
+                "shortDescription": { "text": format!("Undefined behavior: {kind}") },
+                "helpUri": format!("{REPOSITORY}/blob/main/introduction.md"),
+            })
+        })
+        .collect();
+
+    let mut sarif_results = Vec::new();
+    for result in results {
+        let Outcome::Ub(diagnostic) = &result.outcome else {
+            continue;
+        };
+        let kind = UbKind::classify(&diagnostic.message);
+        let spans = diagnostic.spans(root);
+        let (primary, related) = match spans.split_first() {
+            Some((primary, related)) => (primary.clone(), related.to_vec()),
+            // Miri always points at the code, but fall back on the test.
+            None => {
+                let span = Span {
+                    path: format!("{EXAMPLES_DIR}/{}/src/lib.rs", result.example.name),
+                    line: *result.test.lines.start(),
+                    column: 1,
+                    message: String::new(),
+                };
+                (span, Vec::new())
+            }
+        };
+
+        let mut sarif_result = json!({
+            "ruleId": kind.name(),
+            "ruleIndex": UbKind::ALL.iter().position(|k| *k == kind).unwrap(),
+            "level": "error",
+            "message": {
+                "text": format!(
+                    "{}::{}: Undefined Behavior: {}",
+                    result.example.name, result.test.path, diagnostic.message
+                ),
+            },
+            "locations": [location(&primary)],
+            "relatedLocations": related
+                .iter()
+                .enumerate()
+                .map(|(i, span)| {
+                    let mut location = location(span);
+                    location["id"] = json!(i + 1);
+                    location["message"] = json!({ "text": span.message });
+                    location
+                })
+                .collect::<Vec<_>>(),
+            "properties": {
+                "test": format!("{}::{}", result.example.name, result.test.path),
+                "expected": result.expected(),
+                "verdict": result.verdict.to_string(),
+            },
+        });
+        if result.verdict == Verdict::Pass {
+            sarif_result["suppressions"] = json!([{
+                "kind": "inSource",
+                "justification": "the test declares this UB with #[expect_ub]",
+            }]);
+        }
+        sarif_results.push(sarif_result);
+    }
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cargo zoo",
+                    "informationUri": REPOSITORY,
+                    "rules": rules,
+                },
+            },
+            "originalUriBaseIds": {
+                "%SRCROOT%": { "uri": format!("file://{}/", root.display()) },
+            },
+            "results": sarif_results,
+        }],
+    });
+    let mut out = serde_json::to_string_pretty(&log)?;
+    out.push('\n');
+    Ok(out)
+}
+
+fn location(span: &Span) -> Value {
+    json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": span.path, "uriBaseId": "%SRCROOT%" },
+            "region": { "startLine": span.line, "startColumn": span.column },
+        },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use zoo_harness::Expectation;
+
+    use crate::example::Zoo;
+    use crate::miri::parse_diagnostic;
+    use crate::snapshot;
+
+    use super::*;
+
+    fn test_fn(name: &str, line: usize, expectation: Expectation) -> TestFn {
+        TestFn {
+            path: name.to_owned(),
+            name: name.to_owned(),
+            lines: line..=line + 5,
+            expectation: Some(expectation),
+            ignored: false,
+        }
+    }
+
+    /// A declared UB that Miri found, a clean test, a test that found UB it
+    /// didn't declare, and one that never ran.
+    fn results(example: &Example) -> Vec<TestResult<'_>> {
+        let snapshot = snapshot::path(example, &example.source.tests[0]);
+        let stderr = std::fs::read_to_string(snapshot).unwrap();
+        let ub = Outcome::Ub(parse_diagnostic(&stderr).unwrap());
+        let declared = Expectation::Ub {
+            kind: UbKind::InvalidValue,
+            message: "encountered a null reference".to_owned(),
+        };
+        [
+            ("declared_ub", declared, ub.clone(), Verdict::Pass),
+            ("clean", Expectation::Clean, Outcome::Clean, Verdict::Pass),
+            ("regression", Expectation::Clean, ub, Verdict::Regression),
+            (
+                "skipped",
+                Expectation::Clean,
+                Outcome::NotRun,
+                Verdict::Skipped,
+            ),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, expectation, outcome, verdict))| TestResult {
+            example,
+            test: test_fn(name, 10 * i + 1, expectation),
+            outcome,
+            verdict,
+        })
+        .collect()
+    }
+
+    fn zoo() -> Zoo {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        Zoo::load(root).unwrap()
+    }
+
+    #[test]
+    fn escapes_xml() {
+        assert_eq!(
+            escape("a <\"b\"> & c\nd\u{1b}"),
+            "a &lt;&quot;b&quot;&gt; &amp; c&#10;d"
+        );
+    }
+
+    #[test]
+    fn writes_junit() {
+        let zoo = zoo();
+        let example = zoo.example("null_reference").unwrap();
+        let xml = junit(&results(example));
+
+        assert!(xml.contains(
+            "<testsuite name=\"null_reference\" tests=\"4\" failures=\"1\" skipped=\"1\">"
+        ));
+        assert_eq!(xml.matches("<failure ").count(), 1);
+        assert!(xml.contains(
+            "<failure message=\"expected clean, got UB: constructing invalid value of type \
+             &amp;u32: encountered a null reference\"/>"
+        ));
+        assert_eq!(xml.matches("<skipped ").count(), 1);
+        assert!(xml.contains("<skipped message=\"not run\"/>"));
+        assert_eq!(xml.matches("<system-err>").count(), 2);
+    }
+
+    #[test]
+    fn writes_sarif() {
+        let zoo = zoo();
+        let example = zoo.example("null_reference").unwrap();
+        let log: Value =
+            serde_json::from_str(&sarif(&zoo.root, &results(example)).unwrap()).unwrap();
+
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        for result in results {
+            assert_eq!(result["ruleId"], "invalid-value");
+            let location = &result["locations"][0]["physicalLocation"];
+            assert_eq!(
+                location["artifactLocation"]["uri"],
+                "unsound_examples/null_reference/src/lib.rs"
+            );
+            assert_eq!(location["region"]["startLine"], 12);
+        }
+
+        // Only the UB the test declared is suppressed.
+        assert_eq!(
+            results[0]["properties"]["test"],
+            "null_reference::declared_ub"
+        );
+        assert_eq!(results[0]["suppressions"][0]["kind"], "inSource");
+        assert_eq!(
+            results[1]["properties"]["test"],
+            "null_reference::regression"
+        );
+        assert!(results[1].get("suppressions").is_none());
+    }
+}
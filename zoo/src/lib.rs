@@ -0,0 +1,26 @@
+//! The Unsound Zoo runner.
+//!
+//! This knows where the examples live, how to read the expectations their
+//! tests declare, how to run them under Miri, and how to decide whether Miri
+//! said the right thing. The `zoo` binary is a thin command line on top.
+
+pub mod catalog;
+pub mod check;
+pub mod classify;
+pub mod codegen;
+pub mod example;
+pub mod explore;
+pub mod export;
+pub mod metadata;
+pub mod miri;
+pub mod model;
+pub mod native;
+pub mod readme;
+pub mod report;
+pub mod site;
+pub mod snapshot;
+pub mod source;
+pub mod toolchain;
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,1341 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use zoo::codegen::Codegen;
+use zoo::example::{Example, Zoo};
+use zoo::explore;
+use zoo::export::{self, TestResult};
+use zoo::metadata::{self, CodegenTable, Metadata, SanitizerTable, ToolchainTable};
+use zoo::miri::{Miri, Outcome, CROSS_TARGETS};
+use zoo::model::{Detection, Model};
+use zoo::native::{Finding, Native, Profile, Sanitizer, Symptom};
+use zoo::readme;
+use zoo::report::{expectation_label, outcome_label, seed_rates, Table, Verdict};
+use zoo::site;
+use zoo::snapshot::{self, Snapshot, Status};
+use zoo::source::TestFn;
+use zoo::toolchain::{self, Change, Nightly};
+use zoo::Result;
+use zoo::{catalog, classify};
+use zoo_harness::{Expectation, UbKind};
+
+/// List, run and explain the Unsound Zoo examples.
+#[derive(Parser)]
+#[command(name = "zoo")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List every example and test, with what each test expects.
+    List,
+    /// Run examples under Miri and compare the results with what the tests
+    /// declare.
+    Run {
+        /// Examples (`invalid_box`) or single tests (`invalid_box::null_box`)
+        /// to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+        /// Print Miri's full diagnostic for every test that reported one.
+        #[arg(short, long)]
+        verbose: bool,
+        /// Run every test in its own Miri invocation, so that one test
+        /// aborting doesn't stop the rest of its example from running.
+        #[arg(long)]
+        isolate: bool,
+        /// Interpret the tests for this target instead of the host.
+        #[arg(long)]
+        target: Option<String>,
+        /// Also write the results as a JUnit XML report to this file.
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
+        /// Also write the UB diagnostics as a SARIF log to this file.
+        #[arg(long, value_name = "FILE")]
+        sarif: Option<PathBuf>,
+    },
+    /// Show a test's source, what it expects, and how to fix it.
+    Explain {
+        /// The test, as `example::test`.
+        spec: String,
+        /// Also run the test under Miri and show the diagnostic.
+        #[arg(long)]
+        run: bool,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Browse the examples in a terminal UI: pick a test, read its source
+    /// and comments, and run it under Miri to see the diagnostic next to the
+    /// line it points at.
+    Explore {
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Check that each test's UB goes away with the example's `sound`
+    /// feature: Miri must report the declared UB without it, and nothing
+    /// with it.
+    Sound {
+        /// Examples or single tests to check. Checks everything if omitted.
+        specs: Vec<String>,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+        /// Interpret the tests for this target instead of the host.
+        #[arg(long)]
+        target: Option<String>,
+    },
+    /// Run each test under each of Miri's memory models, and compare the
+    /// results with the ones recorded in the examples' metadata.
+    Models {
+        /// Examples or single tests to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// Record the results in the metadata instead of comparing.
+        #[arg(long)]
+        record: bool,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Run each test under Miri for 32-bit, 64-bit ARM and big-endian
+    /// targets, and compare the results with the ones recorded in the
+    /// examples' metadata.
+    Targets {
+        /// Examples or single tests to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// Record the results in the metadata instead of comparing.
+        #[arg(long)]
+        record: bool,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Run tests whose outcome depends on Miri's scheduling under many
+    /// `-Zmiri-seed`s, and report how often each outcome came up.
+    Seeds {
+        /// Examples or single tests to sweep. Sweeps every test with a
+        /// `[package.metadata.zoo.seeds]` entry if omitted.
+        specs: Vec<String>,
+        /// How many seeds to try, starting from 0.
+        #[arg(long, default_value_t = 64)]
+        seeds: u64,
+        /// Record the results in the metadata instead of comparing.
+        #[arg(long)]
+        record: bool,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Run each test under the oldest and newest installed nightlies, and
+    /// where the outcome differs, bisect across the nightlies in between to
+    /// find where it changed.
+    Drift {
+        /// Examples or single tests to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// The nightlies to compare, by rustup name. Uses every installed
+        /// nightly with Miri if omitted.
+        #[arg(long = "toolchain")]
+        toolchains: Vec<String>,
+        /// Record the results in the metadata instead of comparing.
+        #[arg(long)]
+        record: bool,
+    },
+    /// Run each test natively under each of the sanitizers (nightly, x86_64
+    /// Linux only), next to what Miri found, and compare the results with
+    /// the ones recorded in the examples' metadata.
+    Sanitizers {
+        /// Examples or single tests to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// Record the results in the metadata instead of comparing.
+        #[arg(long)]
+        record: bool,
+        /// The nightly toolchain to build with.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Run each test natively at every optimization level, with and
+    /// without LTO, and show what it visibly did at each.
+    Symptoms {
+        /// Examples or single tests to run. Runs everything if omitted.
+        specs: Vec<String>,
+        /// The toolchain to build with.
+        #[arg(long, default_value = "stable")]
+        toolchain: String,
+        /// Print the panic message behind each `panic` and `wrong value`.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// Show the annotations rustc hands LLVM for each example's functions,
+    /// and compare the ones on the functions listed in the examples'
+    /// metadata with what's recorded there.
+    Codegen {
+        /// Examples to build. Builds everything if omitted.
+        specs: Vec<String>,
+        /// Record the annotations on the listed functions in the metadata
+        /// instead of comparing.
+        #[arg(long)]
+        record: bool,
+        /// Also print each function's IR, as rustc generated it.
+        #[arg(long)]
+        ir: bool,
+        /// Also print each function's optimized assembly.
+        #[arg(long)]
+        asm: bool,
+        /// The toolchain to build with.
+        #[arg(long, default_value = "stable")]
+        toolchain: String,
+    },
+    /// Compare what Miri says about each test with the `.stderr` snapshots
+    /// stored next to the examples.
+    Snapshot {
+        /// Examples or single tests to check. Checks everything if omitted.
+        specs: Vec<String>,
+        /// Overwrite the snapshots with the new output instead of failing.
+        #[arg(long)]
+        bless: bool,
+        /// The toolchain Miri is installed for.
+        #[arg(long, default_value = "nightly")]
+        toolchain: String,
+    },
+    /// Regenerate the README's list of examples from their metadata.
+    Readme {
+        /// Don't write anything; fail if README.md is out of date.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Check that every example follows the zoo's conventions: package names,
+    /// metadata, notes on soundness, snapshots, and links in the documents.
+    Check,
+    /// Read Miri's output from some other project and find the zoo tests
+    /// whose diagnostics look most like each undefined behavior in it.
+    Classify {
+        /// The log to read. Reads standard input if omitted.
+        log: Option<PathBuf>,
+        /// How many matches to show for each error.
+        #[arg(long, default_value_t = 5)]
+        limit: usize,
+    },
+    /// Build the GitHub Pages site: the README, the introduction, and a
+    /// page for each example with its notes, code, diagnostics and fix.
+    Site {
+        /// Where to write the site, relative to the workspace root.
+        #[arg(long, default_value = site::SITE_DIR)]
+        out: PathBuf,
+    },
+    /// Write catalog.json: every example and test with its permanent ID.
+    Catalog {
+        /// Don't write anything; fail if catalog.json is out of date.
+        #[arg(long)]
+        check: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<ExitCode> {
+    let zoo = Zoo::discover()?;
+    match command {
+        Command::List => {
+            list(&zoo);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Run {
+            specs,
+            toolchain,
+            verbose,
+            isolate,
+            target,
+            junit,
+            sarif,
+        } => {
+            let options = RunOptions {
+                verbose,
+                isolate,
+                junit,
+                sarif,
+            };
+            let miri = Miri {
+                target,
+                ..Miri::new(toolchain)
+            };
+            run_tests(&zoo, &specs, &miri, &options)
+        }
+        Command::Explain {
+            spec,
+            run,
+            toolchain,
+        } => {
+            let miri = run.then_some(Miri::new(toolchain));
+            explain(&zoo, &spec, miri.as_ref())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Explore { toolchain } => {
+            explore::run(&zoo, Miri::new(toolchain))?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Sound {
+            specs,
+            toolchain,
+            target,
+        } => {
+            let miri = Miri {
+                target,
+                ..Miri::new(toolchain)
+            };
+            check_sound(&zoo, &specs, &miri)
+        }
+        Command::Models {
+            specs,
+            record,
+            toolchain,
+        } => {
+            let models = Model::ALL.map(|model| {
+                let miri = Miri {
+                    flags: model.flags().iter().map(|&flag| flag.to_owned()).collect(),
+                    ..Miri::new(toolchain.clone())
+                };
+                (model, miri)
+            });
+            let matrix = Matrix {
+                key: "models",
+                columns: &models,
+                recorded: |metadata| &metadata.models,
+            };
+            check_matrix(&zoo, &specs, matrix, record)
+        }
+        Command::Targets {
+            specs,
+            record,
+            toolchain,
+        } => {
+            let targets = CROSS_TARGETS.map(|target| {
+                let miri = Miri {
+                    target: Some(target.to_owned()),
+                    ..Miri::new(toolchain.clone())
+                };
+                (target.to_owned(), miri)
+            });
+            let matrix = Matrix {
+                key: "targets",
+                columns: &targets,
+                recorded: |metadata| &metadata.targets,
+            };
+            check_matrix(&zoo, &specs, matrix, record)
+        }
+        Command::Seeds {
+            specs,
+            seeds,
+            record,
+            toolchain,
+        } => check_seeds(&zoo, &specs, &Miri::new(toolchain), seeds, record),
+        Command::Drift {
+            specs,
+            toolchains,
+            record,
+        } => {
+            let nightlies = if toolchains.is_empty() {
+                toolchain::installed()?
+            } else {
+                toolchain::resolve(&toolchains)?
+            };
+            check_drift(&zoo, &specs, &nightlies, record)
+        }
+        Command::Sanitizers {
+            specs,
+            record,
+            toolchain,
+        } => check_sanitizers(&zoo, &specs, &Native::new(toolchain), record),
+        Command::Symptoms {
+            specs,
+            toolchain,
+            verbose,
+        } => {
+            show_symptoms(&zoo, &specs, &Native::new(toolchain), verbose)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Codegen {
+            specs,
+            record,
+            ir,
+            asm,
+            toolchain,
+        } => {
+            let options = CodegenOptions { record, ir, asm };
+            check_codegen(&zoo, &specs, &Codegen::new(toolchain), options)
+        }
+        Command::Snapshot {
+            specs,
+            bless,
+            toolchain,
+        } => check_snapshots(&zoo, &specs, &Miri::new(toolchain), bless),
+        Command::Readme { check } => update_readme(&zoo, check),
+        Command::Check => check(&zoo),
+        Command::Classify { log, limit } => classify_log(&zoo, log.as_deref(), limit),
+        Command::Site { out } => {
+            let out = zoo.root.join(out);
+            let files = site::write(&zoo, &out)?;
+            println!("wrote {files} files to {}", out.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Catalog { check } => update_catalog(&zoo, check),
+    }
+}
+
+fn list(zoo: &Zoo) {
+    let mut table = Table::new(["ID", "TEST", "EXPECTS", "SOURCE"]);
+    for example in &zoo.examples {
+        for test in &example.source.tests {
+            let id = example
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.test_ids.get(&test.path))
+                .map_or("-", String::as_str);
+            let expects = if test.ignored {
+                "ignored".to_owned()
+            } else {
+                expectation_label(test.expectation.as_ref())
+            };
+            table.push([
+                id.to_owned(),
+                format!("{}::{}", example.name, test.path),
+                expects,
+                location(zoo, example, test),
+            ]);
+        }
+    }
+    print!("{table}");
+}
+
+#[derive(Debug, Clone)]
+struct RunOptions {
+    verbose: bool,
+    isolate: bool,
+    junit: Option<PathBuf>,
+    sarif: Option<PathBuf>,
+}
+
+fn run_tests(zoo: &Zoo, specs: &[String], miri: &Miri, options: &RunOptions) -> Result<ExitCode> {
+    let selections = select(zoo, specs)?;
+
+    let mut table = Table::new(["TEST", "RESULT", "EXPECTED", "VERDICT"]);
+    let mut details = Vec::new();
+    let mut regressions = 0;
+    let mut results = Vec::new();
+    for (example, test) in selections {
+        eprintln!("running {}", example.name);
+        let run = match test {
+            None if options.isolate => miri.run_isolated(&zoo.root, example)?,
+            _ => miri.run(&zoo.root, example, test)?,
+        };
+        for (test, outcome) in &run.outcomes {
+            let verdict = Verdict::new(test.expectation.as_ref(), outcome);
+            let name = format!("{}::{}", example.name, test.path);
+            table.push([
+                name.clone(),
+                outcome_label(outcome),
+                expectation_label(test.expectation.as_ref()),
+                verdict.to_string(),
+            ]);
+
+            if verdict == Verdict::Regression {
+                regressions += 1;
+                let expected = test
+                    .expectation
+                    .as_ref()
+                    .map_or("nothing declared".to_owned(), ToString::to_string);
+                details.push(format!(
+                    "{name}\n  expected: {expected}\n  actual:   {}",
+                    outcome_detail(outcome)
+                ));
+            } else if options.verbose {
+                if let Outcome::Ub(diagnostic) = outcome {
+                    details.push(format!("{name}\n{}", diagnostic.text));
+                }
+            }
+            results.push(TestResult {
+                example,
+                test: test.clone(),
+                outcome: outcome.clone(),
+                verdict,
+            });
+        }
+    }
+
+    if let Some(path) = &options.junit {
+        std::fs::write(path, export::junit(&results))
+            .map_err(|e| format!("writing {}: {e}", path.display()))?;
+    }
+    if let Some(path) = &options.sarif {
+        std::fs::write(path, export::sarif(&zoo.root, &results)?)
+            .map_err(|e| format!("writing {}: {e}", path.display()))?;
+    }
+
+    print!("{table}");
+    for detail in details {
+        println!("\n{detail}");
+    }
+    if regressions > 0 {
+        println!("\n{regressions} regression(s)");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn explain(zoo: &Zoo, spec: &str, miri: Option<&Miri>) -> Result<()> {
+    let (example, test) = zoo.resolve(spec)?;
+    let test = test.ok_or("explain needs a single test, as `example::test`")?;
+    let source = &example.source;
+
+    println!("{}::{}", example.name, test.path);
+    println!("  source:  {}", location(zoo, example, test));
+    let expects = test
+        .expectation
+        .as_ref()
+        .map_or("nothing declared".to_owned(), ToString::to_string);
+    println!("  expects: {expects}");
+    println!();
+
+    let excerpt = source.excerpt(test);
+    for (number, line) in excerpt.clone().zip(source.lines(excerpt)) {
+        println!("{number:>4} | {line}");
+    }
+
+    if let Some(notes) = source.soundness_notes(test) {
+        println!("\nTo make this code sound:\n");
+        for line in notes.lines() {
+            println!("  {line}");
+        }
+    }
+
+    if let Some(miri) = miri {
+        let run = miri.run(&zoo.root, example, Some(test))?;
+        let (_, outcome) = &run.outcomes[0];
+        println!("\nMiri says: {}", outcome_label(outcome));
+        match outcome {
+            Outcome::Ub(diagnostic) => println!("\n{}", diagnostic.text),
+            Outcome::Error(error) => println!("\n{error}"),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_sound(zoo: &Zoo, specs: &[String], miri: &Miri) -> Result<ExitCode> {
+    let sound = miri.sound();
+
+    let mut table = Table::new(["TEST", "UNSOUND", "SOUND", "VERDICT"]);
+    let mut details = Vec::new();
+    let mut regressions = 0;
+    for (example, test) in select(zoo, specs)? {
+        eprintln!("running {}", example.name);
+        let (unsound_run, sound_run) = match test {
+            // Every UB test would stop the unsound run, so each one needs a
+            // run of its own; the sound run should get through them all.
+            None => (
+                miri.run_isolated(&zoo.root, example)?,
+                sound.run(&zoo.root, example, None)?,
+            ),
+            Some(test) => (
+                miri.run(&zoo.root, example, Some(test))?,
+                sound.run(&zoo.root, example, Some(test))?,
+            ),
+        };
+
+        // Pair the runs up by test, rather than by position, in case one of
+        // them left a test out.
+        let mut sound_outcomes: BTreeMap<&str, &Outcome> = sound_run
+            .outcomes
+            .iter()
+            .map(|(test, outcome)| (test.path.as_str(), outcome))
+            .collect();
+        for (test, unsound) in &unsound_run.outcomes {
+            let sound = sound_outcomes.remove(test.path.as_str()).ok_or_else(|| {
+                format!(
+                    "`{}::{}` is missing from the run with the `sound` feature",
+                    example.name, test.path
+                )
+            })?;
+            let verdict = Verdict::new(test.expectation.as_ref(), unsound)
+                .and(Verdict::new(Some(&Expectation::Clean), sound));
+            let name = format!("{}::{}", example.name, test.path);
+            table.push([
+                name.clone(),
+                outcome_label(unsound),
+                outcome_label(sound),
+                verdict.to_string(),
+            ]);
+
+            if verdict == Verdict::Regression {
+                regressions += 1;
+                let expected = test
+                    .expectation
+                    .as_ref()
+                    .map_or("nothing declared".to_owned(), ToString::to_string);
+                details.push(format!(
+                    "{name}\n  expected: {expected}, then clean\n  unsound:  {}\n  sound:    {}",
+                    outcome_detail(unsound),
+                    outcome_detail(sound)
+                ));
+            }
+        }
+        if let Some(test) = sound_outcomes.keys().next() {
+            return Err(format!(
+                "`{}::{test}` is missing from the run without the `sound` feature",
+                example.name
+            )
+            .into());
+        }
+    }
+
+    print!("{table}");
+    for detail in details {
+        println!("\n{detail}");
+    }
+    if regressions > 0 {
+        println!("\n{regressions} regression(s)");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// A set of Miri configurations to run every test under, one per column,
+/// and where their results are recorded.
+struct Matrix<'a, K> {
+    /// The `[package.metadata.zoo.<key>]` tables the results go in.
+    key: &'a str,
+    columns: &'a [(K, Miri)],
+    recorded: fn(&Metadata) -> &BTreeMap<String, BTreeMap<K, Detection>>,
+}
+
+fn check_matrix<K: Clone + Ord + fmt::Display>(
+    zoo: &Zoo,
+    specs: &[String],
+    matrix: Matrix<K>,
+    record: bool,
+) -> Result<ExitCode> {
+    let mut headers = vec!["TEST".to_owned()];
+    headers.extend(matrix.columns.iter().map(|(column, _)| column.to_string()));
+    let mut table = Table::new(headers);
+    let mut details = Vec::new();
+
+    for (example, test) in select(zoo, specs)? {
+        let mut found: BTreeMap<String, BTreeMap<K, Detection>> = BTreeMap::new();
+        for (column, miri) in matrix.columns {
+            eprintln!("running {} under {column}", example.name);
+            let run = match test {
+                Some(test) => miri.run(&zoo.root, example, Some(test))?,
+                None => miri.run_isolated(&zoo.root, example)?,
+            };
+            for (test, outcome) in &run.outcomes {
+                if let Some(detection) = Detection::from_outcome(outcome) {
+                    found
+                        .entry(test.path.clone())
+                        .or_default()
+                        .insert(column.clone(), detection);
+                }
+            }
+        }
+
+        let recorded = example.metadata.as_ref().map(matrix.recorded);
+        for test in &example.source.tests {
+            let path = &test.path;
+            let Some(detections) = found.get(path) else {
+                continue;
+            };
+            let mut row = vec![format!("{}::{path}", example.name)];
+            row.extend(matrix.columns.iter().map(|(column, _)| {
+                detections
+                    .get(column)
+                    .map_or("-".to_owned(), ToString::to_string)
+            }));
+            table.push(row);
+
+            let previous = recorded.and_then(|recorded| recorded.get(path));
+            if !record && previous != Some(detections) {
+                let describe = |detections: Option<&_>| match detections {
+                    Some(detections) => describe_results(detections),
+                    None => "nothing".to_owned(),
+                };
+                details.push(format!(
+                    "{}::{path}\n  recorded: {}\n  actual:   {}",
+                    example.name,
+                    describe(previous),
+                    describe(Some(detections)),
+                ));
+            }
+        }
+
+        if record {
+            // Keep what was recorded for tests that weren't run this time.
+            let mut results = recorded.cloned().unwrap_or_default();
+            if test.is_none() {
+                results.clear();
+            }
+            results.extend(found);
+            let tests: Vec<&str> = example
+                .source
+                .tests
+                .iter()
+                .map(|test| test.path.as_str())
+                .collect();
+            let manifest_path = example.dir.join("Cargo.toml");
+            metadata::write_results(&manifest_path, matrix.key, &tests, &results)?;
+        }
+    }
+
+    Ok(report(
+        &table,
+        &details,
+        "test(s) differ from the recorded results",
+    ))
+}
+
+fn check_seeds(
+    zoo: &Zoo,
+    specs: &[String],
+    miri: &Miri,
+    seeds: u64,
+    record: bool,
+) -> Result<ExitCode> {
+    let mut selected = Vec::new();
+    for (example, test) in select(zoo, specs)? {
+        let declared = example.metadata.as_ref().map(|metadata| &metadata.seeds);
+        match test {
+            Some(test) => selected.push((example, test)),
+            None => selected.extend(
+                example
+                    .source
+                    .tests
+                    .iter()
+                    .filter(|test| {
+                        declared.is_some_and(|declared| declared.contains_key(&test.path))
+                    })
+                    .map(|test| (example, test)),
+            ),
+        }
+    }
+    if selected.is_empty() {
+        return Err(
+            "no tests to sweep; name some, or add them to [package.metadata.zoo.seeds]".into(),
+        );
+    }
+
+    let runs = u32::try_from(seeds).map_err(|_| "too many seeds")?;
+    let mut table = Table::new(["TEST", "FLAGS", "RESULTS"]);
+    let mut details = Vec::new();
+    for (example, test) in selected {
+        let recorded = example
+            .metadata
+            .as_ref()
+            .and_then(|metadata| metadata.seeds.get(&test.path))
+            .cloned()
+            .unwrap_or_default();
+        eprintln!(
+            "running {}::{} under {seeds} seeds",
+            example.name, test.path
+        );
+        let sweep = Miri {
+            flags: recorded.flags.clone(),
+            ..miri.clone()
+        };
+        let results = sweep.sweep(&zoo.root, example, test, 0..seeds)?;
+
+        let name = format!("{}::{}", example.name, test.path);
+        let flags = if recorded.flags.is_empty() {
+            "-".to_owned()
+        } else {
+            recorded.flags.join(" ")
+        };
+        table.push([name.clone(), flags, seed_rates(&results, runs)]);
+
+        if record {
+            metadata::write_seeds(&example.dir.join("Cargo.toml"), &test.path, runs, &results)?;
+        } else if (recorded.runs, &recorded.results) != (runs, &results) {
+            details.push(format!(
+                "{name}\n  recorded: {}\n  actual:   {}",
+                seed_rates(&recorded.results, recorded.runs),
+                seed_rates(&results, runs),
+            ));
+        }
+    }
+
+    Ok(report(
+        &table,
+        &details,
+        "test(s) differ from the recorded results",
+    ))
+}
+
+fn check_drift(
+    zoo: &Zoo,
+    specs: &[String],
+    nightlies: &[Nightly],
+    record: bool,
+) -> Result<ExitCode> {
+    let (Some(oldest), Some(newest)) = (nightlies.first(), nightlies.last()) else {
+        return Err("no nightly toolchains with Miri are installed".into());
+    };
+    let mut headers = vec!["TEST".to_owned(), oldest.date.clone()];
+    if nightlies.len() > 1 {
+        headers.push(newest.date.clone());
+    }
+    headers.push("CHANGED".to_owned());
+    let mut table = Table::new(headers);
+    let mut details = Vec::new();
+
+    for (example, test) in select(zoo, specs)? {
+        let tests: Vec<&TestFn> = match test {
+            Some(test) => vec![test],
+            None => example
+                .source
+                .tests
+                .iter()
+                .filter(|test| !test.ignored)
+                .collect(),
+        };
+        let recorded = example
+            .metadata
+            .as_ref()
+            .map(|metadata| &metadata.toolchains);
+
+        let mut found = ToolchainTable::new();
+        for test in tests {
+            let name = format!("{}::{}", example.name, test.path);
+            let drift = toolchain::drift(nightlies.len(), |index| {
+                let nightly = &nightlies[index];
+                eprintln!("running {name} under {}", nightly.name);
+                let run = Miri::new(nightly.name.clone()).run(&zoo.root, example, Some(test))?;
+                // A nightly that can't build the workspace says nothing
+                // about what Miri finds.
+                Ok(run
+                    .started()
+                    .then(|| Detection::from_outcome(&run.outcomes[0].1))
+                    .flatten())
+            })?;
+            let mut row = vec![name.clone()];
+            row.extend(drift.cells(nightlies.len()));
+
+            let observed: BTreeMap<String, Detection> = drift
+                .outcomes
+                .iter()
+                .filter_map(|(&index, detection)| {
+                    Some((nightlies[index].date.clone(), (*detection)?))
+                })
+                .collect();
+            let previous = recorded.and_then(|recorded| recorded.get(&test.path));
+            for (date, detection) in &observed {
+                match previous.and_then(|previous| previous.get(date)) {
+                    Some(was) if !record && was != detection => details.push(format!(
+                        "{name}\n  recorded under {date}: {was}\n  actual:              {detection}"
+                    )),
+                    _ => {}
+                }
+            }
+
+            // The change this run found, along with the recorded ones from
+            // outside the nightlies it covered, so that changes since
+            // nightlies that aren't installed any more show up too.
+            let mut history = previous.cloned().unwrap_or_default();
+            history.extend(observed.clone());
+            let covered = |date: &String| {
+                drift
+                    .oldest
+                    .is_some_and(|oldest| (&nightlies[oldest].date..=&newest.date).contains(&date))
+            };
+            let mut changes: Vec<Change> = toolchain::changes(&history)
+                .into_iter()
+                .filter(|change| !(covered(&change.before) && covered(&change.after)))
+                .chain(drift.change(nightlies))
+                .collect();
+            changes.sort_by(|a, b| a.before.cmp(&b.before));
+            let changes: Vec<String> = changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{} → {} ({} → {})",
+                        change.before, change.after, change.from, change.to
+                    )
+                })
+                .collect();
+            row.push(if changes.is_empty() {
+                "-".to_owned()
+            } else {
+                changes.join(", ")
+            });
+            table.push(row);
+
+            if !observed.is_empty() {
+                found.insert(test.path.clone(), observed);
+            }
+        }
+
+        if record && !found.is_empty() {
+            // Keep what was recorded under other nightlies.
+            let mut results = recorded.cloned().unwrap_or_default();
+            for (test, detections) in found {
+                results.entry(test).or_default().extend(detections);
+            }
+            let tests: Vec<&str> = example
+                .source
+                .tests
+                .iter()
+                .map(|test| test.path.as_str())
+                .collect();
+            let manifest_path = example.dir.join("Cargo.toml");
+            metadata::write_results(&manifest_path, "toolchains", &tests, &results)?;
+        }
+    }
+
+    Ok(report(
+        &table,
+        &details,
+        "result(s) differ from the ones recorded under the same nightly",
+    ))
+}
+
+/// Print a table of results, and then each of the `details` of how they
+/// differ from the recorded ones, failing if there are any. `differences`
+/// says what differs, e.g. "test(s) differ from the recorded results".
+fn report(table: &Table, details: &[String], differences: &str) -> ExitCode {
+    print!("{table}");
+    for detail in details {
+        println!("\n{detail}");
+    }
+    if details.is_empty() {
+        return ExitCode::SUCCESS;
+    }
+    println!(
+        "\n{} {differences}; run with --record to update them",
+        details.len()
+    );
+    ExitCode::FAILURE
+}
+
+fn check_sanitizers(
+    zoo: &Zoo,
+    specs: &[String],
+    native: &Native,
+    record: bool,
+) -> Result<ExitCode> {
+    let mut headers = vec!["TEST", "MIRI"];
+    headers.extend(Sanitizer::ALL.iter().map(|sanitizer| sanitizer.name()));
+    let mut table = Table::new(headers);
+    let mut details = Vec::new();
+
+    for (example, test) in select(zoo, specs)? {
+        let mut found = SanitizerTable::new();
+        for sanitizer in Sanitizer::ALL {
+            eprintln!("running {} under the {sanitizer} sanitizer", example.name);
+            let native = Native {
+                sanitizer: Some(sanitizer),
+                ..native.clone()
+            };
+            for (test, outcome) in &native.run(&zoo.root, example, test)?.outcomes {
+                if let Some(finding) = Finding::from_outcome(outcome) {
+                    found
+                        .entry(test.path.clone())
+                        .or_default()
+                        .insert(sanitizer, finding);
+                }
+            }
+        }
+
+        let metadata = example.metadata.as_ref();
+        let recorded = metadata.map(|metadata| &metadata.sanitizers);
+        for test in &example.source.tests {
+            let path = &test.path;
+            let Some(findings) = found.get(path) else {
+                continue;
+            };
+            let miri = metadata
+                .and_then(|metadata| metadata.models.get(path))
+                .and_then(|detections| detections.get(&Model::StackedBorrows))
+                .map_or("-".to_owned(), ToString::to_string);
+            let mut row = vec![format!("{}::{path}", example.name), miri];
+            row.extend(Sanitizer::ALL.iter().map(|sanitizer| {
+                findings
+                    .get(sanitizer)
+                    .map_or("-".to_owned(), ToString::to_string)
+            }));
+            table.push(row);
+
+            let previous = recorded.and_then(|recorded| recorded.get(path));
+            if !record && previous != Some(findings) {
+                let describe = |findings: Option<&_>| match findings {
+                    Some(findings) => describe_results(findings),
+                    None => "nothing".to_owned(),
+                };
+                details.push(format!(
+                    "{}::{path}\n  recorded: {}\n  actual:   {}",
+                    example.name,
+                    describe(previous),
+                    describe(Some(findings)),
+                ));
+            }
+        }
+
+        if record {
+            // Keep what was recorded for tests that weren't run this time.
+            let mut sanitizers = recorded.cloned().unwrap_or_default();
+            if test.is_none() {
+                sanitizers.clear();
+            }
+            sanitizers.extend(found);
+            let tests: Vec<&str> = example
+                .source
+                .tests
+                .iter()
+                .map(|test| test.path.as_str())
+                .collect();
+            let manifest_path = example.dir.join("Cargo.toml");
+            metadata::write_results(&manifest_path, "sanitizers", &tests, &sanitizers)?;
+        }
+    }
+
+    Ok(report(
+        &table,
+        &details,
+        "test(s) differ from the recorded results",
+    ))
+}
+
+fn show_symptoms(zoo: &Zoo, specs: &[String], native: &Native, verbose: bool) -> Result<()> {
+    let profiles = Profile::matrix();
+    let mut headers = vec!["TEST".to_owned()];
+    headers.extend(profiles.iter().map(ToString::to_string));
+    let mut table = Table::new(headers);
+    let mut messages = Vec::new();
+
+    for (example, test) in select(zoo, specs)? {
+        let mut symptoms: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for &profile in &profiles {
+            eprintln!("running {} at {profile}", example.name);
+            let native = Native {
+                profile: Some(profile),
+                ..native.clone()
+            };
+            for (test, outcome) in &native.run(&zoo.root, example, test)?.outcomes {
+                let Some(symptom) = Symptom::from_outcome(outcome) else {
+                    continue;
+                };
+                if let (Symptom::WrongValue | Symptom::Panic, Outcome::Error(message)) =
+                    (&symptom, outcome)
+                {
+                    messages.push(format!(
+                        "{}::{} at {profile}: {message}",
+                        example.name, test.path
+                    ));
+                }
+                symptoms
+                    .entry(test.path.clone())
+                    .or_default()
+                    .push(symptom.to_string());
+            }
+        }
+
+        for test in &example.source.tests {
+            if let Some(row) = symptoms.remove(&test.path) {
+                table.push(std::iter::once(format!("{}::{}", example.name, test.path)).chain(row));
+            }
+        }
+    }
+
+    print!("{table}");
+    if verbose && !messages.is_empty() {
+        println!();
+        for message in &messages {
+            println!("{message}");
+        }
+    }
+    Ok(())
+}
+
+struct CodegenOptions {
+    record: bool,
+    ir: bool,
+    asm: bool,
+}
+
+fn check_codegen(
+    zoo: &Zoo,
+    specs: &[String],
+    codegen: &Codegen,
+    options: CodegenOptions,
+) -> Result<ExitCode> {
+    let mut examples: Vec<&Example> = Vec::new();
+    for (example, _) in select(zoo, specs)? {
+        if !examples.iter().any(|seen| seen.name == example.name) {
+            examples.push(example);
+        }
+    }
+
+    let mut table = Table::new(["FUNCTION", "ANNOTATIONS"]);
+    let mut listings = Vec::new();
+    let mut details = Vec::new();
+    for example in examples {
+        eprintln!("building {}", example.name);
+        let functions = codegen.ir(&zoo.root, example)?;
+        let mut found = CodegenTable::new();
+        for function in &functions {
+            let name = format!("{}::{}", example.name, function.name);
+            let mut facts = function.facts.iter();
+            table.push([name.as_str(), facts.next().map_or("-", String::as_str)]);
+            for fact in facts {
+                table.push(["", fact.as_str()]);
+            }
+            if options.ir {
+                listings.push(format!("{name} (IR):\n{}", function.text));
+            }
+        }
+        // Only the functions whose promises the example's UB breaks are
+        // recorded; the rest are mostly panics and assertions.
+        let recorded = example.metadata.as_ref().map(|metadata| &metadata.codegen);
+        for listed in recorded.into_iter().flat_map(BTreeMap::keys) {
+            let facts = functions
+                .iter()
+                .find(|function| function.name == *listed)
+                .map(|function| function.facts.clone())
+                .unwrap_or_default();
+            found.insert(listed.clone(), facts);
+        }
+        if options.asm {
+            for function in codegen.asm(&zoo.root, example)? {
+                listings.push(format!(
+                    "{}::{} (assembly):\n{}",
+                    example.name, function.name, function.text
+                ));
+            }
+        }
+
+        if options.record {
+            metadata::write_codegen(&example.dir.join("Cargo.toml"), &found)?;
+        } else if recorded != Some(&found) {
+            details.push(format!(
+                "{}\n  recorded: {:?}\n  actual:   {:?}",
+                example.name,
+                recorded.cloned().unwrap_or_default(),
+                found
+            ));
+        }
+    }
+
+    for listing in &listings {
+        println!("{listing}\n");
+    }
+    Ok(report(
+        &table,
+        &details,
+        "example(s) differ from the recorded annotations",
+    ))
+}
+
+fn describe_results<K: fmt::Display, V: fmt::Display>(results: &BTreeMap<K, V>) -> String {
+    results
+        .iter()
+        .map(|(column, result)| format!("{column} = {result}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn check_snapshots(zoo: &Zoo, specs: &[String], miri: &Miri, bless: bool) -> Result<ExitCode> {
+    let selections = select(zoo, specs)?;
+
+    let mut table = Table::new(["TEST", "SNAPSHOT"]);
+    let mut diffs = Vec::new();
+    let mut mismatches = 0;
+    let mut check = |name: String, snapshot: Snapshot| -> Result<()> {
+        let status = snapshot.status();
+        if status != Status::Unchanged {
+            if bless {
+                snapshot.bless()?;
+            } else {
+                mismatches += 1;
+                diffs.push(format!(
+                    "{}\n{}",
+                    zoo.relative(&snapshot.path).display(),
+                    snapshot.diff()
+                ));
+            }
+        }
+        let label = match (bless, status) {
+            (true, Status::Changed | Status::New) => "blessed".to_owned(),
+            (true, Status::Stale) => "removed".to_owned(),
+            _ => status.to_string(),
+        };
+        table.push([name, label]);
+        Ok(())
+    };
+
+    for (example, test) in selections {
+        eprintln!("running {}", example.name);
+        let tests = match test {
+            Some(test) => vec![test],
+            None => example.source.tests.iter().collect(),
+        };
+        for test in tests.into_iter().filter(|test| !test.ignored) {
+            let run = miri.run(&zoo.root, example, Some(test))?;
+            let snapshot = Snapshot::new(&zoo.root, example, test, &run.stderr)?;
+            check(format!("{}::{}", example.name, test.path), snapshot)?;
+        }
+        if test.is_none() {
+            for orphan in snapshot::orphans(example)? {
+                let name = zoo.relative(&orphan.path).display().to_string();
+                check(name, orphan)?;
+            }
+        }
+    }
+
+    print!("{table}");
+    for diff in diffs {
+        println!("\n{diff}");
+    }
+    if mismatches > 0 {
+        println!("\n{mismatches} snapshot(s) out of date; run with --bless to update them");
+        return Ok(ExitCode::FAILURE);
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Resolve the command line's `example` and `example::test` arguments,
+/// selecting every example if there are none.
+fn select<'a>(zoo: &'a Zoo, specs: &[String]) -> Result<Vec<(&'a Example, Option<&'a TestFn>)>> {
+    if specs.is_empty() {
+        return Ok(zoo.examples.iter().map(|example| (example, None)).collect());
+    }
+    specs.iter().map(|spec| zoo.resolve(spec)).collect()
+}
+
+fn update_readme(zoo: &Zoo, check: bool) -> Result<ExitCode> {
+    let path = zoo.root.join("README.md");
+    let current = std::fs::read_to_string(&path)?;
+    let updated = readme::splice(&current, &readme::contents(zoo)?)?;
+
+    if current == updated {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if check {
+        eprintln!("README.md is out of date; run `cargo zoo readme`");
+        return Ok(ExitCode::FAILURE);
+    }
+    std::fs::write(&path, updated)?;
+    println!("updated README.md");
+    Ok(ExitCode::SUCCESS)
+}
+
+fn check(zoo: &Zoo) -> Result<ExitCode> {
+    let problems = zoo::check::check(zoo)?;
+    for problem in &problems {
+        println!("{problem}");
+    }
+    if !problems.is_empty() {
+        println!("\n{} problem(s)", problems.len());
+        return Ok(ExitCode::FAILURE);
+    }
+    println!("{} examples checked, no problems", zoo.examples.len());
+    Ok(ExitCode::SUCCESS)
+}
+
+fn classify_log(zoo: &Zoo, log: Option<&Path>, limit: usize) -> Result<ExitCode> {
+    let text = match log {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| format!("reading {}: {e}", path.display()))?
+        }
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let cwd = std::env::current_dir()?;
+    let diagnostics = classify::diagnostics(&text, &cwd);
+    if diagnostics.is_empty() {
+        eprintln!("no `error: Undefined Behavior` in the log");
+        return Ok(ExitCode::FAILURE);
+    }
+
+    let rules = classify::rules(zoo)?;
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let kind = UbKind::classify(&diagnostic.message);
+        println!("UB ({kind}): {}", diagnostic.message);
+        let matches = classify::classify(diagnostic, &rules);
+        if matches.is_empty() {
+            println!("  nothing in the zoo looks like this");
+            continue;
+        }
+        let mut table = Table::new(["MATCH", "ID", "TEST", "WHY"]);
+        for m in matches.iter().take(limit) {
+            let mut why = Vec::new();
+            if m.same_kind {
+                why.push(format!("{kind}"));
+            }
+            why.push(format!("message {:.0}%", m.message * 100.0));
+            let (shared, notes) = m.notes;
+            if notes > 0 {
+                why.push(format!("notes {shared}/{notes}"));
+            }
+            table.push([
+                format!("{:.0}%", m.score * 100.0),
+                m.rule.id.clone(),
+                m.rule.test.clone(),
+                why.join(", "),
+            ]);
+        }
+        print!("{table}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn update_catalog(zoo: &Zoo, check: bool) -> Result<ExitCode> {
+    let path = zoo.root.join(catalog::CATALOG);
+    let current = match std::fs::read_to_string(&path) {
+        Ok(current) => current,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("reading {}: {e}", path.display()).into()),
+    };
+    let updated = catalog::render(zoo)?;
+
+    if current == updated {
+        return Ok(ExitCode::SUCCESS);
+    }
+    if check {
+        eprintln!(
+            "{} is out of date; run `cargo zoo catalog`",
+            catalog::CATALOG
+        );
+        return Ok(ExitCode::FAILURE);
+    }
+    std::fs::write(&path, updated)?;
+    println!("updated {}", catalog::CATALOG);
+    Ok(ExitCode::SUCCESS)
+}
+
+fn location(zoo: &Zoo, example: &Example, test: &TestFn) -> String {
+    format!(
+        "{}:{}",
+        zoo.relative(&example.source.path).display(),
+        test.lines.start()
+    )
+}
+
+fn outcome_detail(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Ub(diagnostic) => format!("UB: {}", diagnostic.message),
+        Outcome::Error(error) => format!("error: {error}"),
+        other => outcome_label(other),
+    }
+}
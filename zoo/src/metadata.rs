@@ -0,0 +1,335 @@
+//! The `[package.metadata.zoo]` section of each example's `Cargo.toml`.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table};
+use zoo_harness::UbKind;
+
+use crate::model::{Detection, Model};
+use crate::native::{Finding, Sanitizer};
+use crate::Result;
+
+/// What Miri found under each model, for each test (by libtest name).
+pub type ModelTable = BTreeMap<String, BTreeMap<Model, Detection>>;
+
+/// What Miri found for each target (by triple), for each test.
+pub type TargetTable = BTreeMap<String, BTreeMap<String, Detection>>;
+
+/// What Miri found under each nightly (by the date of the commit it was
+/// built from), for each test.
+pub type ToolchainTable = BTreeMap<String, BTreeMap<String, Detection>>;
+
+/// What each sanitizer found, for each test (by libtest name).
+pub type SanitizerTable = BTreeMap<String, BTreeMap<Sanitizer, Finding>>;
+
+/// The annotations on each function (by path within the example), as
+/// described by [`codegen::Function::facts`](crate::codegen::Function::facts).
+pub type CodegenTable = BTreeMap<String, Vec<String>>;
+
+/// Machine-readable facts about an example.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Metadata {
+    /// The example's permanent ID, e.g. `UZ-0007`. IDs are never reused or
+    /// changed, even if the example is renamed.
+    #[serde(deserialize_with = "example_id")]
+    pub id: String,
+    /// The title shown in the README, e.g. "Constructing a malformed `bool`".
+    pub title: String,
+    pub category: Category,
+    /// Where the example goes in the README's list of its category, which
+    /// runs from the simplest ideas to the ones that build on them.
+    pub order: u32,
+    /// The kind of undefined behavior the example is about.
+    #[serde(deserialize_with = "ub_kind")]
+    pub ub_class: UbKind,
+    /// Whether Miri reports the undefined behavior.
+    pub miri_detects: bool,
+    /// A link to the relevant part of the Rust Reference.
+    pub reference: String,
+    /// The libtest names of the example's tests.
+    pub tests: Vec<String>,
+    /// Each test's permanent ID: the example's, plus a number, e.g.
+    /// `UZ-0007.2`.
+    #[serde(default)]
+    pub test_ids: BTreeMap<String, String>,
+    /// What each test does under each of Miri's models, as recorded by
+    /// `cargo zoo models --record`.
+    #[serde(default)]
+    pub models: ModelTable,
+    /// What each sanitizer makes of each test, as recorded by
+    /// `cargo zoo sanitizers --record`.
+    #[serde(default)]
+    pub sanitizers: SanitizerTable,
+    /// What Miri finds for each of [`CROSS_TARGETS`](crate::miri::CROSS_TARGETS),
+    /// as recorded by `cargo zoo targets --record`.
+    #[serde(default)]
+    pub targets: TargetTable,
+    /// What Miri finds under each nightly it has been run with, as recorded
+    /// by `cargo zoo drift --record`.
+    #[serde(default)]
+    pub toolchains: ToolchainTable,
+    /// The annotations rustc gives LLVM on the functions whose promises
+    /// the example's UB breaks. The functions are listed by hand, and their
+    /// annotations recorded by `cargo zoo codegen --record`.
+    #[serde(default)]
+    pub codegen: CodegenTable,
+    /// The tests whose outcome depends on Miri's seed, by libtest name.
+    #[serde(default)]
+    pub seeds: BTreeMap<String, SeedSweep>,
+}
+
+/// How a test fares across many `-Zmiri-seed`s.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeedSweep {
+    /// Extra `MIRIFLAGS` for the sweep, e.g. to turn off a check that
+    /// would otherwise stop the test before the interesting part.
+    #[serde(default)]
+    pub flags: Vec<String>,
+    /// How many seeds were tried, as recorded by `cargo zoo seeds --record`.
+    #[serde(default)]
+    pub runs: u32,
+    /// How many of them ended each way, keyed like a [`Detection`].
+    #[serde(default)]
+    pub results: BTreeMap<String, u32>,
+}
+
+/// How much background an example needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// A single mistake, visible in a few lines of code.
+    Simple,
+    /// A mistake that only shows up in the context of a larger design.
+    Complex,
+}
+
+impl Category {
+    pub const ALL: [Category; 2] = [Category::Simple, Category::Complex];
+
+    pub fn heading(self) -> &'static str {
+        match self {
+            Category::Simple => "Simple unsound examples",
+            Category::Complex => "Complex unsound examples",
+        }
+    }
+}
+
+/// Replace the `[package.metadata.zoo.<key>]` tables, e.g. `models`, in a
+/// `Cargo.toml`, leaving the rest of the file as it was. `tests` gives the
+/// order to write them in.
+pub fn write_results<K: fmt::Display, V: fmt::Display>(
+    manifest_path: &Path,
+    key: &str,
+    tests: &[&str],
+    results: &BTreeMap<String, BTreeMap<K, V>>,
+) -> Result<()> {
+    let mut table = Table::new();
+    table.set_implicit(true);
+    for &test in tests {
+        let Some(columns) = results.get(test) else {
+            continue;
+        };
+        let mut row = Table::new();
+        for (column, result) in columns {
+            row[&column.to_string()] = toml_edit::value(result.to_string());
+        }
+        table[test] = Item::Table(row);
+    }
+    write_item(manifest_path, key, Item::Table(table))
+}
+
+/// Replace the `[package.metadata.zoo.codegen]` table in a `Cargo.toml`,
+/// or remove it if `codegen` is empty.
+pub fn write_codegen(manifest_path: &Path, codegen: &CodegenTable) -> Result<()> {
+    if codegen.is_empty() {
+        return edit_zoo(manifest_path, |zoo| {
+            zoo.remove("codegen");
+            Ok(())
+        });
+    }
+    let mut table = Table::new();
+    for (function, facts) in codegen {
+        table[function.as_str()] = toml_edit::value(facts.iter().collect::<Array>());
+    }
+    write_item(manifest_path, "codegen", Item::Table(table))
+}
+
+/// Record the results of a seed sweep in `[package.metadata.zoo.seeds]`,
+/// keeping the test's `flags`.
+pub fn write_seeds(
+    manifest_path: &Path,
+    test: &str,
+    runs: u32,
+    results: &BTreeMap<String, u32>,
+) -> Result<()> {
+    edit_zoo(manifest_path, |zoo| {
+        let seeds = zoo
+            .entry("seeds")
+            .or_insert_with(|| {
+                let mut table = Table::new();
+                table.set_implicit(true);
+                Item::Table(table)
+            })
+            .as_table_mut()
+            .ok_or("[package.metadata.zoo.seeds] is not a table")?;
+        let sweep = seeds
+            .entry(test)
+            .or_insert_with(toml_edit::table)
+            .as_table_mut()
+            .ok_or_else(|| format!("[package.metadata.zoo.seeds.{test:?}] is not a table"))?;
+        sweep["runs"] = toml_edit::value(i64::from(runs));
+        let mut counts = InlineTable::new();
+        for (result, count) in results {
+            counts.insert(result, i64::from(*count).into());
+        }
+        sweep["results"] = toml_edit::value(counts);
+        Ok(())
+    })
+}
+
+fn write_item(manifest_path: &Path, key: &str, item: Item) -> Result<()> {
+    edit_zoo(manifest_path, |zoo| {
+        zoo[key] = item;
+        Ok(())
+    })
+}
+
+/// Apply `edit` to the `[package.metadata.zoo]` table of a `Cargo.toml`,
+/// leaving the rest of the file as it was.
+fn edit_zoo(manifest_path: &Path, edit: impl FnOnce(&mut Table) -> Result<()>) -> Result<()> {
+    let text = std::fs::read_to_string(manifest_path)
+        .map_err(|e| format!("reading {}: {e}", manifest_path.display()))?;
+    let mut manifest: DocumentMut = text
+        .parse()
+        .map_err(|e| format!("parsing {}: {e}", manifest_path.display()))?;
+
+    let zoo = manifest["package"]["metadata"]["zoo"]
+        .as_table_mut()
+        .ok_or_else(|| format!("{} has no [package.metadata.zoo]", manifest_path.display()))?;
+    edit(zoo)?;
+
+    std::fs::write(manifest_path, manifest.to_string())
+        .map_err(|e| format!("writing {}: {e}", manifest_path.display()))?;
+    Ok(())
+}
+
+/// Whether `id` looks like an example ID, `UZ-` and four digits.
+pub fn is_example_id(id: &str) -> bool {
+    id.strip_prefix("UZ-")
+        .is_some_and(|number| number.len() == 4 && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn example_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    let id = String::deserialize(deserializer)?;
+    if !is_example_id(&id) {
+        return Err(serde::de::Error::custom(format!(
+            "bad example ID `{id}` (expected `UZ-` and four digits)"
+        )));
+    }
+    Ok(id)
+}
+
+fn ub_kind<'de, D: Deserializer<'de>>(deserializer: D) -> Result<UbKind, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map_err(serde::de::Error::custom)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_metadata() {
+        let metadata: Metadata = toml::from_str(
+            r#"
+            id = "UZ-0001"
+            title = "Constructing a malformed `bool`"
+            category = "simple"
+            order = 1
+            ub-class = "invalid-value"
+            miri-detects = true
+            reference = "https://doc.rust-lang.org/reference/behavior-considered-undefined.html"
+            tests = ["transmute_to_bool"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(metadata.category, Category::Simple);
+        assert_eq!(metadata.ub_class, UbKind::InvalidValue);
+        assert_eq!(metadata.tests, ["transmute_to_bool"]);
+    }
+
+    #[test]
+    fn parses_models() {
+        let metadata: Metadata = toml::from_str(
+            r#"
+            id = "UZ-0001"
+            title = "Constructing illegal aliases"
+            category = "simple"
+            order = 1
+            ub-class = "aliasing"
+            miri-detects = true
+            reference = ""
+            tests = ["test::atomic_alias"]
+
+            [models."test::atomic_alias"]
+            stacked-borrows = "aliasing"
+            tree-borrows = "clean"
+
+            [targets."test::atomic_alias"]
+            s390x-unknown-linux-gnu = "aliasing"
+
+            [sanitizers."test::atomic_alias"]
+            address = "clean"
+            thread = "data race"
+            "#,
+        )
+        .unwrap();
+        let models = &metadata.models["test::atomic_alias"];
+        assert_eq!(
+            models[&Model::StackedBorrows],
+            Detection::Ub(UbKind::Aliasing)
+        );
+        assert_eq!(models[&Model::TreeBorrows], Detection::Clean);
+        assert_eq!(
+            metadata.targets["test::atomic_alias"]["s390x-unknown-linux-gnu"],
+            Detection::Ub(UbKind::Aliasing)
+        );
+        let sanitizers = &metadata.sanitizers["test::atomic_alias"];
+        assert_eq!(sanitizers[&Sanitizer::Address], Finding::Clean);
+        assert_eq!(
+            sanitizers[&Sanitizer::Thread],
+            Finding::Report("data race".to_owned())
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_ub_class() {
+        let error = toml::from_str::<Metadata>(
+            r#"
+            id = "UZ-0001"
+            title = "t"
+            category = "simple"
+            order = 1
+            ub-class = "spooky"
+            miri-detects = true
+            reference = ""
+            tests = []
+            "#,
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("unknown UB kind `spooky`"));
+    }
+
+    #[test]
+    fn rejects_bad_ids() {
+        assert!(is_example_id("UZ-0007"));
+        for id in ["UZ-7", "uz-0007", "UZ-00071", "UZ-000x"] {
+            assert!(!is_example_id(id), "{id}");
+        }
+    }
+}
@@ -0,0 +1,494 @@
+//! Running an example's tests under Miri and making sense of the output.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::process::Command;
+
+use crate::example::Example;
+use crate::model::Detection;
+use crate::source::TestFn;
+use crate::Result;
+
+/// What happened to one test when its crate ran under Miri.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// Miri stopped the test with undefined behavior.
+    Ub(Diagnostic),
+    /// The test passed and Miri had nothing to say.
+    Clean,
+    /// The test failed some other way: a panic, an unsupported operation,
+    /// a build error. Holds the first line of the complaint.
+    Error(String),
+    /// The test is marked `#[ignore]`.
+    Ignored,
+    /// The test never started, because an earlier test aborted the test
+    /// binary.
+    NotRun,
+}
+
+/// An "Undefined Behavior" error reported by Miri, or a sanitizer's report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The text following `error: Undefined Behavior: `.
+    pub message: String,
+    /// The whole diagnostic, from the `error:` line up to Miri giving up.
+    pub text: String,
+}
+
+/// A place in the source that a diagnostic points to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Relative to the workspace root.
+    pub path: String,
+    pub line: usize,
+    pub column: usize,
+    /// The text of the `help:` or `note:` that introduced the span, if it
+    /// wasn't the error itself.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// The `--> file:line:column` spans in the diagnostic, in order, each
+    /// with the heading it appeared under. The standard library's are left
+    /// out, since they aren't in the workspace.
+    pub fn spans(&self, root: &Path) -> Vec<Span> {
+        let root = format!("{}/", root.display());
+        let mut spans = Vec::new();
+        let mut heading = "";
+        for line in self.text.lines() {
+            if !line.starts_with(' ') {
+                heading = line;
+                continue;
+            }
+            let Some(location) = line.trim_start().strip_prefix("--> ") else {
+                continue;
+            };
+            let mut parts = location.rsplitn(3, ':');
+            let (Some(column), Some(number), Some(path)) =
+                (parts.next(), parts.next(), parts.next())
+            else {
+                continue;
+            };
+            let path = path.strip_prefix(&root).unwrap_or(path);
+            if path.starts_with('/') || path.starts_with('$') {
+                continue;
+            }
+            let (Ok(line), Ok(column)) = (number.parse(), column.parse()) else {
+                continue;
+            };
+            let message = match heading.split_once(": ") {
+                Some(("help" | "note", message)) => message.to_owned(),
+                _ => String::new(),
+            };
+            spans.push(Span {
+                path: path.to_owned(),
+                line,
+                column,
+                message,
+            });
+        }
+        spans
+    }
+}
+
+/// How to invoke Miri.
+#[derive(Debug, Clone)]
+pub struct Miri {
+    /// The rustup toolchain Miri is installed for.
+    pub toolchain: String,
+    /// Cargo features to enable in the example, e.g. `sound`.
+    pub features: Vec<String>,
+    /// Extra flags for Miri itself, passed in `MIRIFLAGS`.
+    pub flags: Vec<String>,
+    /// The target to interpret the tests for, if not the host.
+    pub target: Option<String>,
+}
+
+/// The targets besides the host that the zoo is run for: 32-bit, another
+/// 64-bit architecture, and a big-endian one. Miri interprets them all
+/// without any hardware or cross toolchain.
+pub const CROSS_TARGETS: [&str; 3] = [
+    "i686-unknown-linux-gnu",
+    "aarch64-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+];
+
+impl Default for Miri {
+    fn default() -> Self {
+        Miri {
+            toolchain: "nightly".to_owned(),
+            features: Vec::new(),
+            flags: Vec::new(),
+            target: None,
+        }
+    }
+}
+
+/// The result of running (some of) an example's tests.
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// The outcome of each test that was selected, in source order.
+    pub outcomes: Vec<(TestFn, Outcome)>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Run {
+    /// Whether the test binary ran at all. If it didn't, the crate failed
+    /// to build, and the outcomes are the build's errors.
+    pub fn started(&self) -> bool {
+        started(&self.stdout)
+    }
+}
+
+impl Miri {
+    pub fn new(toolchain: impl Into<String>) -> Miri {
+        Miri {
+            toolchain: toolchain.into(),
+            ..Miri::default()
+        }
+    }
+
+    /// The same Miri, for the examples with their `sound` feature on.
+    pub fn sound(&self) -> Miri {
+        let mut flags = self.flags.clone();
+        // Leaking isn't undefined behavior, and sometimes it's the fix: a
+        // sound `Drain` leaks the rest of the vector when it is leaked.
+        flags.push("-Zmiri-ignore-leaks".to_owned());
+        Miri {
+            features: vec!["sound".to_owned()],
+            flags,
+            ..self.clone()
+        }
+    }
+
+    /// The `cargo miri test` command for an example, optionally narrowed
+    /// down to a single test.
+    pub fn command(&self, root: &Path, example: &Example, test: Option<&TestFn>) -> Command {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(root)
+            // `cargo run` passes down the toolchain it was started with,
+            // which would override the `+toolchain` below.
+            .env_remove("RUSTUP_TOOLCHAIN")
+            .arg(format!("+{}", self.toolchain))
+            .args(["miri", "test", "--lib", "--package", &example.package]);
+        if !self.flags.is_empty() {
+            let mut flags: Vec<String> = std::env::var("MIRIFLAGS")
+                .map(|flags| flags.split_whitespace().map(str::to_owned).collect())
+                .unwrap_or_default();
+            flags.extend(self.flags.iter().cloned());
+            command.env("MIRIFLAGS", flags.join(" "));
+        }
+        if !self.features.is_empty() {
+            command.args(["--features", &self.features.join(",")]);
+        }
+        if let Some(target) = &self.target {
+            command.args(["--target", target]);
+        }
+        command.args(["--", "--test-threads=1"]);
+        if let Some(test) = test {
+            command.args(["--exact", &test.path]);
+        }
+        command
+    }
+
+    /// Run a single test once for each seed in `seeds`, and count how often
+    /// it ended each way (as a [`Detection`]).
+    pub fn sweep(
+        &self,
+        root: &Path,
+        example: &Example,
+        test: &TestFn,
+        seeds: Range<u64>,
+    ) -> Result<BTreeMap<String, u32>> {
+        let mut results = BTreeMap::new();
+        for seed in seeds {
+            let mut miri = self.clone();
+            miri.flags.push(format!("-Zmiri-seed={seed}"));
+            let run = miri.run(root, example, Some(test))?;
+            if let Some(detection) = Detection::from_outcome(&run.outcomes[0].1) {
+                *results.entry(detection.to_string()).or_default() += 1;
+            }
+        }
+        Ok(results)
+    }
+
+    pub fn run(&self, root: &Path, example: &Example, test: Option<&TestFn>) -> Result<Run> {
+        let output = self
+            .command(root, example, test)
+            .output()
+            .map_err(|e| format!("running cargo miri: {e}"))?;
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+        let selected: Vec<&TestFn> = match test {
+            Some(test) => vec![test],
+            None => example.source.tests.iter().collect(),
+        };
+        let outcomes = assign_outcomes(&selected, &stdout, &stderr);
+
+        Ok(Run {
+            outcomes,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Run each of an example's tests in its own Miri invocation.
+    ///
+    /// Miri aborts the whole test binary at the first undefined behavior it
+    /// finds, so a plain [`Miri::run`] only ever observes one UB test per
+    /// example. This is slower, but every test gets an outcome of its own.
+    /// Tests marked `#[ignore]` are not started at all.
+    pub fn run_isolated(&self, root: &Path, example: &Example) -> Result<Run> {
+        let mut isolated = Run {
+            outcomes: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        for test in &example.source.tests {
+            if test.ignored {
+                isolated.outcomes.push((test.clone(), Outcome::Ignored));
+                continue;
+            }
+            let run = self.run(root, example, Some(test))?;
+            isolated.outcomes.extend(run.outcomes);
+            isolated.stdout.push_str(&run.stdout);
+            isolated.stderr.push_str(&run.stderr);
+        }
+        Ok(isolated)
+    }
+}
+
+/// Work out what happened to each selected test from libtest's report on
+/// stdout and Miri's diagnostics on stderr.
+pub fn assign_outcomes(tests: &[&TestFn], stdout: &str, stderr: &str) -> Vec<(TestFn, Outcome)> {
+    let reported = parse_test_lines(stdout);
+    let started = started(stdout);
+
+    tests
+        .iter()
+        .map(|&test| {
+            let outcome = match reported.iter().find(|(name, _)| *name == test.path) {
+                Some((_, TestLine::Ok)) => Outcome::Clean,
+                Some((_, TestLine::Ignored)) => Outcome::Ignored,
+                Some((_, TestLine::Failed)) => Outcome::Error(
+                    panic_message(stdout, &test.path)
+                        .unwrap_or("test failed")
+                        .to_owned(),
+                ),
+                // The test started but never finished: Miri aborted it.
+                Some((_, TestLine::Aborted)) => stderr_outcome(stderr),
+                // The test binary never ran at all, probably because it
+                // failed to build.
+                None if !started => stderr_outcome(stderr),
+                None => Outcome::NotRun,
+            };
+            (test.clone(), outcome)
+        })
+        .collect()
+}
+
+/// Whether libtest's report on stdout has begun.
+fn started(stdout: &str) -> bool {
+    stdout.lines().any(|line| line.starts_with("running "))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TestLine {
+    Ok,
+    Ignored,
+    Failed,
+    Aborted,
+}
+
+/// Parse libtest's `test name ... result` lines.
+fn parse_test_lines(stdout: &str) -> Vec<(&str, TestLine)> {
+    stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("test "))
+        .filter_map(|line| {
+            let line = line.trim_end();
+            if let Some(name) = line.strip_suffix(" ...") {
+                return Some((name, TestLine::Aborted));
+            }
+            let (name, result) = line.split_once(" ... ")?;
+            let result = match result {
+                "ok" => TestLine::Ok,
+                "FAILED" => TestLine::Failed,
+                result if result.starts_with("ignored") => TestLine::Ignored,
+                _ => return None,
+            };
+            Some((name, result))
+        })
+        .collect()
+}
+
+/// Find the message of a panic that failed the test.
+fn panic_message<'a>(stdout: &'a str, test: &str) -> Option<&'a str> {
+    let header = format!("---- {test} stdout ----");
+    let mut lines = stdout.lines().skip_while(|line| *line != header).skip(1);
+    lines.find(|line| line.contains("panicked at"))?;
+    lines.next().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// Classify the first error on stderr.
+pub fn stderr_outcome(stderr: &str) -> Outcome {
+    if let Some(diagnostic) = parse_diagnostic(stderr) {
+        return Outcome::Ub(diagnostic);
+    }
+    let error = stderr
+        .lines()
+        .find(|line| {
+            (line.starts_with("error:") || line.starts_with("error["))
+                && !line.starts_with("error: aborting due to")
+                && !line.starts_with("error: test failed")
+        })
+        .unwrap_or("the test binary exited without a report");
+    Outcome::Error(error.to_owned())
+}
+
+/// Pull Miri's "Undefined Behavior" error out of stderr.
+pub fn parse_diagnostic(stderr: &str) -> Option<Diagnostic> {
+    const PREFIX: &str = "error: Undefined Behavior: ";
+
+    let lines: Vec<&str> = stderr.lines().collect();
+    let start = lines.iter().position(|line| line.starts_with(PREFIX))?;
+    let message = lines[start][PREFIX.len()..].to_owned();
+    let end = lines[start..]
+        .iter()
+        .position(|line| line.starts_with("error: aborting due to"))
+        .map_or(lines.len(), |offset| start + offset);
+    let text = lines[start..end].join("\n").trim_end().to_owned();
+
+    Some(Diagnostic { message, text })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_fn(path: &str) -> TestFn {
+        TestFn {
+            path: path.to_owned(),
+            name: path.rsplit("::").next().unwrap().to_owned(),
+            lines: 1..=1,
+            expectation: None,
+            ignored: false,
+        }
+    }
+
+    const STDERR: &str = "\
+   Compiling invalid_box v0.1.0 (/zoo/unsound_examples/invalid_box)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.19s
+     Running unittests src/lib.rs (target/miri/x86_64-unknown-linux-gnu/debug/deps/invalid_box-17be41ea10b83c2f)
+error: Undefined Behavior: constructing invalid value of type std::boxed::Box<u32>: encountered a dangling box (use-after-free)
+  --> unsound_examples/invalid_box/src/lib.rs:101:5
+   |
+   = help: this indicates a bug in the program: it performed an invalid operation, and caused Undefined Behavior
+
+error: aborting due to 1 previous error
+
+error: test failed, to rerun pass `-p invalid_box --lib`
+";
+
+    #[test]
+    fn attributes_abort_to_the_unfinished_test() {
+        let stdout = "\nrunning 4 tests\ntest a ... ok\ntest b ... ignored, reason\ntest c ... ";
+        let tests = [test_fn("a"), test_fn("b"), test_fn("c"), test_fn("d")];
+        let tests: Vec<_> = tests.iter().collect();
+        let outcomes: Vec<_> = assign_outcomes(&tests, stdout, STDERR)
+            .into_iter()
+            .map(|(_, outcome)| outcome)
+            .collect();
+
+        assert_eq!(outcomes[0], Outcome::Clean);
+        assert_eq!(outcomes[1], Outcome::Ignored);
+        let Outcome::Ub(diagnostic) = &outcomes[2] else {
+            panic!("expected UB, got {:?}", outcomes[2]);
+        };
+        assert_eq!(
+            diagnostic.message,
+            "constructing invalid value of type std::boxed::Box<u32>: encountered a dangling box (use-after-free)"
+        );
+        assert!(diagnostic.text.ends_with("caused Undefined Behavior"));
+        assert_eq!(outcomes[3], Outcome::NotRun);
+    }
+
+    #[test]
+    fn reports_panics() {
+        let stdout = "\
+running 1 test
+test test::see_vec_works ... FAILED
+
+failures:
+
+---- test::see_vec_works stdout ----
+
+thread 'test::see_vec_works' panicked at src/lib.rs:170:5:
+assertion `left == right` failed
+";
+        let test = test_fn("test::see_vec_works");
+        let outcomes = assign_outcomes(&[&test], stdout, "");
+        assert_eq!(
+            outcomes[0].1,
+            Outcome::Error("assertion `left == right` failed".to_owned())
+        );
+    }
+
+    #[test]
+    fn reports_build_failures() {
+        let stderr = "error[E0308]: mismatched types\n  --> src/lib.rs:32:27\n";
+        let test = test_fn("enum_bogus_discriminant2");
+        let outcomes = assign_outcomes(&[&test], "", stderr);
+        assert_eq!(
+            outcomes[0].1,
+            Outcome::Error("error[E0308]: mismatched types".to_owned())
+        );
+    }
+
+    const DIAGNOSTIC: &str = "\
+error: Undefined Behavior: trying to retag from <2381> for Unique permission at alloc1033[0x0], but that tag only grants SharedReadOnly permission for this location
+  --> /zoo/unsound_examples/illegal_aliasing/src/lib.rs:30:32
+   |
+30 |         let x_alias = unsafe { &mut *x_alias };
+   |                                ^^^^^^^^^^^^^ this error occurs as part of retag at alloc1033[0x0..0x4]
+   |
+   = help: this indicates a potential bug in the program: it performed an invalid operation, but the Stacked Borrows rules it violated are still experimental
+help: <2381> was created by a SharedReadOnly retag at offsets [0x0..0x4]
+  --> /zoo/unsound_examples/illegal_aliasing/src/lib.rs:28:23
+   |
+28 |         let x_alias = x.as_ref() as *const u32 as *mut u32;
+   |                       ^^^^^^^^^^
+note: inside `std::ptr::read::<u8>`
+  --> /rustup/lib/rustlib/src/rust/library/core/src/ptr/mod.rs:1712:9";
+
+    #[test]
+    fn finds_spans() {
+        let diagnostic = Diagnostic {
+            message: String::new(),
+            text: DIAGNOSTIC.to_owned(),
+        };
+        let spans = diagnostic.spans(Path::new("/zoo"));
+        assert_eq!(
+            spans,
+            [
+                Span {
+                    path: "unsound_examples/illegal_aliasing/src/lib.rs".to_owned(),
+                    line: 30,
+                    column: 32,
+                    message: String::new(),
+                },
+                Span {
+                    path: "unsound_examples/illegal_aliasing/src/lib.rs".to_owned(),
+                    line: 28,
+                    column: 23,
+                    message: "<2381> was created by a SharedReadOnly retag at offsets [0x0..0x4]"
+                        .to_owned(),
+                },
+            ]
+        );
+    }
+}
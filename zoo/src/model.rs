@@ -0,0 +1,178 @@
+//! The memory models Miri can check a test against, and what each one
+//! found.
+//!
+//! Whether some code is undefined behavior can depend on the rules Miri
+//! enforces: Stacked Borrows and Tree Borrows disagree about some aliasing
+//! patterns, and strict provenance and symbolic alignment checking reject
+//! code that the defaults let through.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+use zoo_harness::UbKind;
+
+use crate::miri::Outcome;
+
+/// A set of rules Miri can check a test against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Model {
+    /// Miri's default aliasing model.
+    StackedBorrows,
+    /// `-Zmiri-tree-borrows`.
+    TreeBorrows,
+    /// `-Zmiri-strict-provenance`: no integer-to-pointer casts.
+    StrictProvenance,
+    /// `-Zmiri-symbolic-alignment-check`: alignment must follow from the
+    /// type, not from where the allocation happened to land.
+    SymbolicAlignment,
+}
+
+impl Model {
+    pub const ALL: [Model; 4] = [
+        Model::StackedBorrows,
+        Model::TreeBorrows,
+        Model::StrictProvenance,
+        Model::SymbolicAlignment,
+    ];
+
+    /// The name used in metadata and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Model::StackedBorrows => "stacked-borrows",
+            Model::TreeBorrows => "tree-borrows",
+            Model::StrictProvenance => "strict-provenance",
+            Model::SymbolicAlignment => "symbolic-alignment",
+        }
+    }
+
+    /// The `MIRIFLAGS` that select this model.
+    pub fn flags(self) -> &'static [&'static str] {
+        match self {
+            Model::StackedBorrows => &[],
+            Model::TreeBorrows => &["-Zmiri-tree-borrows"],
+            Model::StrictProvenance => &["-Zmiri-strict-provenance"],
+            Model::SymbolicAlignment => &["-Zmiri-symbolic-alignment-check"],
+        }
+    }
+}
+
+impl fmt::Display for Model {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Model {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Model, String> {
+        Model::ALL
+            .into_iter()
+            .find(|model| model.name() == name)
+            .ok_or_else(|| {
+                let names: Vec<_> = Model::ALL.iter().map(|model| model.name()).collect();
+                format!(
+                    "unknown model `{name}` (expected one of: {})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for Model {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Model, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// What Miri made of a test under one model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detection {
+    /// Miri reported undefined behavior of this kind.
+    Ub(UbKind),
+    /// The test ran clean.
+    Clean,
+    /// Miri refused to run an operation under this model, e.g. an
+    /// integer-to-pointer cast under strict provenance.
+    Unsupported,
+    /// The test failed some other way.
+    Error,
+}
+
+impl Detection {
+    /// `None` for outcomes that say nothing about the model, because the
+    /// test didn't run.
+    pub fn from_outcome(outcome: &Outcome) -> Option<Detection> {
+        match outcome {
+            Outcome::Ub(diagnostic) => Some(Detection::Ub(UbKind::classify(&diagnostic.message))),
+            Outcome::Clean => Some(Detection::Clean),
+            Outcome::Error(error) if error.starts_with("error: unsupported operation") => {
+                Some(Detection::Unsupported)
+            }
+            Outcome::Error(_) => Some(Detection::Error),
+            Outcome::Ignored | Outcome::NotRun => None,
+        }
+    }
+}
+
+impl fmt::Display for Detection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Detection::Ub(kind) => write!(f, "{kind}"),
+            Detection::Clean => f.write_str("clean"),
+            Detection::Unsupported => f.write_str("unsupported"),
+            Detection::Error => f.write_str("error"),
+        }
+    }
+}
+
+impl FromStr for Detection {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Detection, String> {
+        match name {
+            "clean" => Ok(Detection::Clean),
+            "unsupported" => Ok(Detection::Unsupported),
+            "error" => Ok(Detection::Error),
+            kind => kind.parse().map(Detection::Ub).map_err(|_| {
+                format!(
+                    "unknown detection `{kind}` (expected clean, unsupported, error or a UB kind)"
+                )
+            }),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Detection {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Detection, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for model in Model::ALL {
+            assert_eq!(model.name().parse(), Ok(model));
+        }
+        assert!("weak-memory".parse::<Model>().is_err());
+
+        for detection in [
+            Detection::Clean,
+            Detection::Unsupported,
+            Detection::Error,
+            Detection::Ub(UbKind::Aliasing),
+        ] {
+            assert_eq!(detection.to_string().parse(), Ok(detection));
+        }
+        assert!("spooky".parse::<Detection>().is_err());
+    }
+}
@@ -0,0 +1,454 @@
+//! Running an example's tests as native code, optionally under one of
+//! LLVM's sanitizers.
+//!
+//! Miri is an interpreter; the sanitizers instrument a real build instead,
+//! so they catch a different (and smaller) set of mistakes, but they also
+//! see things Miri can't, like some data races.
+
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::example::Example;
+use crate::miri::{assign_outcomes, Diagnostic, Outcome, Run};
+use crate::source::TestFn;
+use crate::Result;
+
+/// What a sanitizer made of a test.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// The sanitizer reported a bug of this kind, e.g. `data race`.
+    Report(String),
+    Clean,
+    /// The test crashed without the sanitizer noticing anything first.
+    Crash,
+    /// The test failed some other way, e.g. a failed assertion.
+    Error,
+}
+
+impl Finding {
+    /// `None` for outcomes that say nothing, because the test didn't run.
+    pub fn from_outcome(outcome: &Outcome) -> Option<Finding> {
+        match outcome {
+            Outcome::Ub(diagnostic) => {
+                let (_, bug) = diagnostic.message.split_once(": ")?;
+                Some(Finding::Report(bug.to_owned()))
+            }
+            Outcome::Clean => Some(Finding::Clean),
+            Outcome::Error(error) if error.starts_with("crashed") => Some(Finding::Crash),
+            Outcome::Error(_) => Some(Finding::Error),
+            Outcome::Ignored | Outcome::NotRun => None,
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Report(bug) => f.write_str(bug),
+            Finding::Clean => f.write_str("clean"),
+            Finding::Crash => f.write_str("crash"),
+            Finding::Error => f.write_str("error"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Finding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Finding, D::Error> {
+        Ok(match String::deserialize(deserializer)?.as_str() {
+            "clean" => Finding::Clean,
+            "crash" => Finding::Crash,
+            "error" => Finding::Error,
+            bug => Finding::Report(bug.to_owned()),
+        })
+    }
+}
+
+/// What a test visibly did when run natively, without a sanitizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Symptom {
+    /// The test passed: whatever the UB did, nobody noticed.
+    Pass,
+    /// An assertion in the test failed.
+    WrongValue,
+    /// Some other panic.
+    Panic,
+    /// The process was killed by this signal, e.g. `SIGSEGV`.
+    Signal(String),
+    /// Something reported the undefined behavior itself, e.g.
+    /// `AddressSanitizer: heap-use-after-free`.
+    Report(String),
+}
+
+impl Symptom {
+    /// `None` for outcomes that say nothing, because the test didn't run.
+    pub fn from_outcome(outcome: &Outcome) -> Option<Symptom> {
+        match outcome {
+            Outcome::Clean => Some(Symptom::Pass),
+            Outcome::Error(error) => Some(match error.strip_prefix("crashed with ") {
+                Some(signal) => Symptom::Signal(signal.to_owned()),
+                None if error.starts_with("assertion") => Symptom::WrongValue,
+                None => Symptom::Panic,
+            }),
+            // Without a sanitizer nothing should report UB, but keep the
+            // report rather than lose it.
+            Outcome::Ub(diagnostic) => Some(Symptom::Report(diagnostic.message.clone())),
+            Outcome::Ignored | Outcome::NotRun => None,
+        }
+    }
+}
+
+impl fmt::Display for Symptom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Symptom::Pass => f.write_str("pass"),
+            Symptom::WrongValue => f.write_str("wrong value"),
+            Symptom::Panic => f.write_str("panic"),
+            Symptom::Signal(signal) => f.write_str(signal),
+            Symptom::Report(message) => f.write_str(message),
+        }
+    }
+}
+
+/// How hard the optimizer is allowed to work on a native build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Profile {
+    /// `opt-level`, 0 to 3.
+    pub opt_level: u8,
+    /// Fat LTO, across the example, the harness and the standard library's
+    /// generic code.
+    pub lto: bool,
+}
+
+impl Profile {
+    /// Every optimization level, first without LTO and then with it.
+    pub fn matrix() -> Vec<Profile> {
+        [false, true]
+            .into_iter()
+            .flat_map(|lto| (0..=3).map(move |opt_level| Profile { opt_level, lto }))
+            .collect()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "O{}", self.opt_level)?;
+        if self.lto {
+            f.write_str("-lto")?;
+        }
+        Ok(())
+    }
+}
+
+/// The only target the sanitizers are set up for.
+pub const SANITIZER_TARGET: &str = "x86_64-unknown-linux-gnu";
+
+/// One of the sanitizers `rustc -Zsanitizer` supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sanitizer {
+    Address,
+    Memory,
+    Thread,
+}
+
+impl Sanitizer {
+    pub const ALL: [Sanitizer; 3] = [Sanitizer::Address, Sanitizer::Memory, Sanitizer::Thread];
+
+    /// The name `-Zsanitizer` takes, which is also the one used in metadata.
+    pub fn name(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+            Sanitizer::Memory => "memory",
+            Sanitizer::Thread => "thread",
+        }
+    }
+}
+
+impl fmt::Display for Sanitizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Sanitizer {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Sanitizer, String> {
+        Sanitizer::ALL
+            .into_iter()
+            .find(|sanitizer| sanitizer.name() == name)
+            .ok_or_else(|| {
+                format!("unknown sanitizer `{name}` (expected one of: address, memory, thread)")
+            })
+    }
+}
+
+impl<'de> Deserialize<'de> for Sanitizer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Sanitizer, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// How to build and run an example natively.
+#[derive(Debug, Clone)]
+pub struct Native {
+    /// The rustup toolchain to build with; the sanitizers need a nightly.
+    pub toolchain: String,
+    pub sanitizer: Option<Sanitizer>,
+    /// Build with this profile instead of cargo's usual test profile.
+    pub profile: Option<Profile>,
+}
+
+impl Native {
+    pub fn new(toolchain: impl Into<String>) -> Native {
+        Native {
+            toolchain: toolchain.into(),
+            sanitizer: None,
+            profile: None,
+        }
+    }
+
+    /// The `cargo test` command for a single test. Tests that are only
+    /// ignored outside of Miri are run too.
+    pub fn command(&self, root: &Path, example: &Example, test: &TestFn) -> Command {
+        let mut command = Command::new("cargo");
+        command
+            .current_dir(root)
+            .env_remove("RUSTUP_TOOLCHAIN")
+            .arg(format!("+{}", self.toolchain))
+            .arg("test");
+        if let Some(sanitizer) = self.sanitizer {
+            // The standard library has to be instrumented too, and each
+            // sanitizer needs a build of its own.
+            command
+                .env("RUSTFLAGS", format!("-Zsanitizer={sanitizer}"))
+                // Leaks aren't undefined behavior, and several examples leak
+                // on purpose.
+                .env("ASAN_OPTIONS", "detect_leaks=0")
+                .args(["-Zbuild-std", "--target", SANITIZER_TARGET]);
+        }
+        if let Some(profile) = self.profile {
+            // Debug assertions would turn on the standard library's checks
+            // for some UB, and only the optimizer is meant to vary here.
+            command
+                .env(
+                    "CARGO_PROFILE_TEST_OPT_LEVEL",
+                    profile.opt_level.to_string(),
+                )
+                .env(
+                    "CARGO_PROFILE_TEST_LTO",
+                    if profile.lto { "fat" } else { "off" },
+                )
+                .env("CARGO_PROFILE_TEST_DEBUG_ASSERTIONS", "false");
+        }
+        // Each combination needs a build of its own; keep them apart so
+        // that switching between them doesn't rebuild everything.
+        let mut target_dir = match self.sanitizer {
+            Some(sanitizer) => Some(root.join("target/sanitizer").join(sanitizer.name())),
+            None => self.profile.map(|_| root.join("target/native")),
+        };
+        if let (Some(dir), Some(profile)) = (&mut target_dir, self.profile) {
+            dir.push(profile.to_string());
+        }
+        if let Some(target_dir) = target_dir {
+            command.arg("--target-dir").arg(target_dir);
+        }
+        command
+            .args(["--lib", "--package", &example.package])
+            .args(["--", "--include-ignored", "--test-threads=1"])
+            .args(["--exact", &test.path]);
+        command
+    }
+
+    /// Run each of an example's tests (or just `only`) in a process of its
+    /// own, since any of them may crash. Tests marked `#[ignore]` are not
+    /// started at all.
+    pub fn run(&self, root: &Path, example: &Example, only: Option<&TestFn>) -> Result<Run> {
+        if self.sanitizer.is_some() && !cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            return Err("the sanitizers are only supported on x86_64 Linux".into());
+        }
+
+        let mut run = Run {
+            outcomes: Vec::new(),
+            stdout: String::new(),
+            stderr: String::new(),
+        };
+        for test in &example.source.tests {
+            if only.is_some_and(|only| only.path != test.path) {
+                continue;
+            }
+            if test.ignored {
+                run.outcomes.push((test.clone(), Outcome::Ignored));
+                continue;
+            }
+            let output = self
+                .command(root, example, test)
+                .output()
+                .map_err(|e| format!("running cargo test: {e}"))?;
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+
+            run.outcomes
+                .push((test.clone(), native_outcome(test, &stdout, &stderr)));
+            run.stdout.push_str(&stdout);
+            run.stderr.push_str(&stderr);
+        }
+        Ok(run)
+    }
+}
+
+/// Work out what happened to a test that ran natively: a sanitizer report
+/// wins, then a crash, then whatever libtest said.
+pub fn native_outcome(test: &TestFn, stdout: &str, stderr: &str) -> Outcome {
+    if let Some(diagnostic) = parse_sanitizer_report(stderr) {
+        // The sanitizers also catch fatal signals, but a crash isn't them
+        // finding anything.
+        let (_, bug) = diagnostic.message.split_once(": ").unwrap();
+        if ["SEGV", "BUS", "FPE", "ILL", "ABRT"].contains(&bug) {
+            return Outcome::Error(format!("crashed with SIG{bug}"));
+        }
+        return Outcome::Ub(diagnostic);
+    }
+    if let Some(signal) = parse_signal(stderr) {
+        return Outcome::Error(format!("crashed with {signal}"));
+    }
+    let (_, outcome) = assign_outcomes(&[test], stdout, stderr).remove(0);
+    outcome
+}
+
+/// Pull a sanitizer's `ERROR:` or `WARNING:` report out of stderr. The
+/// message is the kind of bug the sanitizer found, e.g.
+/// `AddressSanitizer: heap-use-after-free`.
+pub fn parse_sanitizer_report(stderr: &str) -> Option<Diagnostic> {
+    static REPORT: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"(?:ERROR|WARNING): (\w+Sanitizer): ([^(\n]+?)(?: on | \(|\n|$)").unwrap()
+    });
+
+    let captures = REPORT.captures(stderr)?;
+    let start = captures.get(0).unwrap().start();
+    let message = format!("{}: {}", &captures[1], captures[2].trim());
+    let text = stderr[start..]
+        .lines()
+        .take_while(|line| !line.starts_with("error: test failed"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_owned();
+    Some(Diagnostic { message, text })
+}
+
+/// The signal that killed the test binary, if one did.
+fn parse_signal(stderr: &str) -> Option<&str> {
+    static SIGNAL: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(r"\(signal: \d+, (SIG\w+)").unwrap());
+    SIGNAL
+        .captures(stderr)
+        .map(|captures| captures.get(1).unwrap().as_str())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_fn(path: &str) -> TestFn {
+        TestFn {
+            path: path.to_owned(),
+            name: path.to_owned(),
+            lines: 1..=1,
+            expectation: None,
+            ignored: false,
+        }
+    }
+
+    #[test]
+    fn reads_sanitizer_reports() {
+        let stderr = "\
+=================================================================
+==1502==ERROR: AddressSanitizer: attempting double-free on 0x602000000010 in thread T1:
+    #0 0x55cc7822a5d8 in free
+SUMMARY: AddressSanitizer: double-free
+==1502==ABORTING
+error: test failed, to rerun pass `-p invalid_box --lib`
+";
+        let diagnostic = parse_sanitizer_report(stderr).unwrap();
+        assert_eq!(
+            diagnostic.message,
+            "AddressSanitizer: attempting double-free"
+        );
+        assert!(diagnostic.text.ends_with("==1502==ABORTING"));
+
+        let stderr = "==2940==WARNING: ThreadSanitizer: data race (pid=2940)\n";
+        assert_eq!(
+            parse_sanitizer_report(stderr).unwrap().message,
+            "ThreadSanitizer: data race"
+        );
+        let stderr = "==2967==WARNING: MemorySanitizer: use-of-uninitialized-value\n";
+        assert_eq!(
+            parse_sanitizer_report(stderr).unwrap().message,
+            "MemorySanitizer: use-of-uninitialized-value"
+        );
+    }
+
+    #[test]
+    fn classifies_symptoms() {
+        let symptom = |error: &str| Symptom::from_outcome(&Outcome::Error(error.to_owned()));
+        assert_eq!(
+            symptom("assertion `left == right` failed"),
+            Some(Symptom::WrongValue)
+        );
+        assert_eq!(
+            symptom("crashed with SIGSEGV"),
+            Some(Symptom::Signal("SIGSEGV".to_owned()))
+        );
+        assert_eq!(symptom("index out of bounds"), Some(Symptom::Panic));
+        assert_eq!(Symptom::from_outcome(&Outcome::Clean), Some(Symptom::Pass));
+        assert_eq!(Symptom::from_outcome(&Outcome::Ignored), None);
+        let stderr = "==1==ERROR: AddressSanitizer: heap-use-after-free on address 0x1\n";
+        let report = Outcome::Ub(parse_sanitizer_report(stderr).unwrap());
+        assert_eq!(
+            Symptom::from_outcome(&report),
+            Some(Symptom::Report(
+                "AddressSanitizer: heap-use-after-free".to_owned()
+            ))
+        );
+
+        let names: Vec<String> = Profile::matrix().iter().map(ToString::to_string).collect();
+        assert_eq!(
+            names,
+            ["O0", "O1", "O2", "O3", "O0-lto", "O1-lto", "O2-lto", "O3-lto"]
+        );
+    }
+
+    #[test]
+    fn reports_crashes() {
+        let stderr = "\
+free(): double free detected in tcache 2
+error: test failed, to rerun pass `-p invalid_box --lib`
+
+Caused by:
+  process didn't exit successfully: `invalid_box-99b04939684f2512 --exact dangling_box` (signal: 6, SIGABRT: process abort signal)
+";
+        let stdout = "running 1 test\ntest dangling_box ... ";
+        assert_eq!(
+            native_outcome(&test_fn("dangling_box"), stdout, stderr),
+            Outcome::Error("crashed with SIGABRT".to_owned())
+        );
+        let stderr = "==7==ERROR: AddressSanitizer: SEGV on unknown address 0x000000000000\n";
+        assert_eq!(
+            native_outcome(&test_fn("null_box"), "", stderr),
+            Outcome::Error("crashed with SIGSEGV".to_owned())
+        );
+        let stdout = "running 1 test\ntest dangling_box ... ok\n";
+        assert_eq!(
+            native_outcome(&test_fn("dangling_box"), stdout, ""),
+            Outcome::Clean
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Generating the README's table of contents from the example metadata.
+
+use std::fmt::Write;
+
+use crate::example::Zoo;
+use crate::metadata::Category;
+use crate::miri::CROSS_TARGETS;
+use crate::model::Model;
+use crate::report::seed_rates;
+use crate::Result;
+
+/// The generated part of the README sits between these two lines.
+pub const BEGIN: &str =
+    "<!-- zoo:begin (generated by `cargo zoo readme`; edit each example's Cargo.toml instead) -->";
+pub const END: &str = "<!-- zoo:end -->";
+
+/// The list of examples, grouped by category.
+pub fn contents(zoo: &Zoo) -> Result<String> {
+    let mut out = String::new();
+    for category in Category::ALL {
+        let mut entries = Vec::new();
+        for example in &zoo.examples {
+            let metadata = example.metadata.as_ref().ok_or_else(|| {
+                format!("`{}` has no [package.metadata.zoo] section", example.name)
+            })?;
+            if metadata.category == category {
+                let entry = format!(
+                    "- [{}]({}) ({})",
+                    metadata.title,
+                    example.source_url(),
+                    metadata.id
+                );
+                entries.push((metadata.order, entry));
+            }
+        }
+        entries.sort_by_key(|(order, _)| *order);
+        if entries.is_empty() {
+            continue;
+        }
+
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        writeln!(out, "**{}**\n", category.heading())?;
+        for (_, entry) in entries {
+            writeln!(out, "{entry}")?;
+        }
+    }
+    out.push_str(&model_notes(zoo)?);
+    out.push_str(&target_notes(zoo)?);
+    out.push_str(&seed_notes(zoo)?);
+    out.push_str(&codegen_notes(zoo)?);
+    Ok(out)
+}
+
+/// Which tests Miri treats differently depending on the memory model, as
+/// recorded by `cargo zoo models`.
+fn model_notes(zoo: &Zoo) -> Result<String> {
+    let mut recorded = false;
+    let mut entries = Vec::new();
+    for example in &zoo.examples {
+        let Some(metadata) = &example.metadata else {
+            continue;
+        };
+        for test in &example.source.tests {
+            let Some(detections) = metadata.models.get(&test.path) else {
+                continue;
+            };
+            recorded = true;
+            let Some(default) = detections.get(&Model::StackedBorrows) else {
+                continue;
+            };
+            let differences: Vec<String> = detections
+                .iter()
+                .filter(|(_, detection)| *detection != default)
+                .map(|(model, detection)| format!("{detection} under {model}"))
+                .collect();
+            if !differences.is_empty() {
+                entries.push(format!(
+                    "- `{}::{}` ({default} by default): {}",
+                    example.name,
+                    test.path,
+                    differences.join(", ")
+                ));
+            }
+        }
+    }
+    if !recorded {
+        return Ok(String::new());
+    }
+
+    let models: Vec<&str> = Model::ALL.iter().map(|model| model.name()).collect();
+    let (last, rest) = models.split_last().unwrap();
+    let mut out = format!(
+        "\n**Miri's memory models**\n\n\
+         Every test is run under {} and {last} (see `cargo zoo models`).",
+        rest.join(", ")
+    );
+    if entries.is_empty() {
+        out.push_str(" The models agree on every test.\n");
+    } else {
+        out.push_str(" The models agree on every test except:\n\n");
+        for entry in entries {
+            writeln!(out, "{entry}")?;
+        }
+    }
+    Ok(out)
+}
+
+/// Which tests Miri treats differently on other targets, as recorded by
+/// `cargo zoo targets`.
+fn target_notes(zoo: &Zoo) -> Result<String> {
+    let mut recorded = false;
+    let mut entries = Vec::new();
+    for example in &zoo.examples {
+        let Some(metadata) = &example.metadata else {
+            continue;
+        };
+        for test in &example.source.tests {
+            let Some(detections) = metadata.targets.get(&test.path) else {
+                continue;
+            };
+            recorded = true;
+            let Some(host) = metadata
+                .models
+                .get(&test.path)
+                .and_then(|detections| detections.get(&Model::StackedBorrows))
+            else {
+                continue;
+            };
+            let differences: Vec<String> = detections
+                .iter()
+                .filter(|(_, detection)| *detection != host)
+                .map(|(target, detection)| format!("{detection} on {target}"))
+                .collect();
+            if !differences.is_empty() {
+                entries.push(format!(
+                    "- `{}::{}` ({host} on the host): {}",
+                    example.name,
+                    test.path,
+                    differences.join(", ")
+                ));
+            }
+        }
+    }
+    if !recorded {
+        return Ok(String::new());
+    }
+
+    let (last, rest) = CROSS_TARGETS.split_last().unwrap();
+    let mut out = format!(
+        "\n**Other targets**\n\n\
+         Every test is also run under Miri for {} and {last} (see `cargo zoo targets`).",
+        rest.join(", ")
+    );
+    if entries.is_empty() {
+        out.push_str(" Miri finds the same on every target as on the host.\n");
+    } else {
+        out.push_str(" Miri finds the same as on the host except:\n\n");
+        for entry in entries {
+            writeln!(out, "{entry}")?;
+        }
+    }
+    Ok(out)
+}
+
+/// How the seed-sensitive tests fared, as recorded by `cargo zoo seeds`.
+fn seed_notes(zoo: &Zoo) -> Result<String> {
+    let mut entries = Vec::new();
+    for example in &zoo.examples {
+        let Some(metadata) = &example.metadata else {
+            continue;
+        };
+        for (test, sweep) in &metadata.seeds {
+            let flags = if sweep.flags.is_empty() {
+                String::new()
+            } else {
+                format!(" (with `{}`)", sweep.flags.join(" "))
+            };
+            entries.push(format!(
+                "- `{}::{test}`{flags}: {}",
+                example.name,
+                seed_rates(&sweep.results, sweep.runs)
+            ));
+        }
+    }
+    if entries.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut out = String::from(
+        "\n**Scheduling**\n\n\
+         Miri picks a thread schedule from a random seed, so these tests are run \
+         under many seeds (see `cargo zoo seeds`):\n\n",
+    );
+    for entry in entries {
+        writeln!(out, "{entry}")?;
+    }
+    Ok(out)
+}
+
+/// What rustc promises LLVM about the examples' functions, as recorded by
+/// `cargo zoo codegen`.
+fn codegen_notes(zoo: &Zoo) -> Result<String> {
+    let mut entries = Vec::new();
+    for example in &zoo.examples {
+        let Some(metadata) = &example.metadata else {
+            continue;
+        };
+        for (function, facts) in &metadata.codegen {
+            let facts: Vec<String> = facts.iter().map(|fact| format!("`{fact}`")).collect();
+            entries.push(format!(
+                "- `{}::{function}`: {}",
+                example.name,
+                facts.join(", ")
+            ));
+        }
+    }
+    if entries.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut out = String::from(
+        "\n**What the optimizer is told**\n\n\
+         rustc annotates the code it hands to LLVM with what the types promise, and \
+         the optimizer is free to rely on it. These are the annotations on the \
+         functions whose promises the examples break (see `cargo zoo codegen`):\n\n",
+    );
+    for entry in entries {
+        writeln!(out, "{entry}")?;
+    }
+    Ok(out)
+}
+
+/// Replace the generated section of `readme` with `contents`.
+pub fn splice(readme: &str, contents: &str) -> Result<String> {
+    let (before, rest) = readme
+        .split_once(BEGIN)
+        .ok_or("README.md has no `<!-- zoo:begin` marker")?;
+    let (_, after) = rest
+        .split_once(END)
+        .ok_or("README.md has no `<!-- zoo:end -->` marker")?;
+    Ok(format!("{before}{BEGIN}\n{contents}{END}{after}"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn splice_replaces_only_the_generated_section() {
+        let readme = format!("# Title\n\n{BEGIN}\nstale\n{END}\n\nfooter\n");
+        assert_eq!(
+            splice(&readme, "- fresh\n").unwrap(),
+            format!("# Title\n\n{BEGIN}\n- fresh\n{END}\n\nfooter\n")
+        );
+        assert!(splice("# Title\n", "").is_err());
+    }
+
+    #[test]
+    fn readme_is_up_to_date() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let readme = std::fs::read_to_string(root.join("README.md")).unwrap();
+        let expected = splice(&readme, &contents(&zoo).unwrap()).unwrap();
+        assert!(
+            readme == expected,
+            "README.md is out of date; run `cargo zoo readme`"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Comparing outcomes with expectations, and printing the results.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use zoo_harness::{Expectation, UbKind};
+
+use crate::miri::Outcome;
+
+/// Whether a test did what it declared it would.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The outcome matches the declared expectation.
+    Pass,
+    /// The outcome doesn't match: the test found no UB, the wrong UB, or
+    /// failed for some other reason.
+    Regression,
+    /// The test didn't run, so there's nothing to compare.
+    Skipped,
+    /// The test ran, but doesn't declare what it expects.
+    Undeclared,
+}
+
+impl Verdict {
+    pub fn new(expectation: Option<&Expectation>, outcome: &Outcome) -> Verdict {
+        let ub_message = match outcome {
+            Outcome::Ignored | Outcome::NotRun => return Verdict::Skipped,
+            Outcome::Error(_) => return Verdict::Regression,
+            Outcome::Ub(diagnostic) => Some(diagnostic.message.as_str()),
+            Outcome::Clean => None,
+        };
+        match expectation {
+            None => Verdict::Undeclared,
+            Some(expectation) if expectation.is_met_by(ub_message) => Verdict::Pass,
+            Some(_) => Verdict::Regression,
+        }
+    }
+}
+
+impl Verdict {
+    /// The verdict for a test that has to pass two checks: the worse of the
+    /// two.
+    pub fn and(self, other: Verdict) -> Verdict {
+        use Verdict::*;
+        match (self, other) {
+            (Regression, _) | (_, Regression) => Regression,
+            (Undeclared, _) | (_, Undeclared) => Undeclared,
+            (Skipped, _) | (_, Skipped) => Skipped,
+            (Pass, Pass) => Pass,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Pass => "ok",
+            Verdict::Regression => "REGRESSION",
+            Verdict::Skipped => "-",
+            Verdict::Undeclared => "undeclared",
+        })
+    }
+}
+
+/// A short description of an outcome, for a table cell.
+pub fn outcome_label(outcome: &Outcome) -> String {
+    match outcome {
+        Outcome::Ub(diagnostic) => {
+            format!("UB detected ({})", UbKind::classify(&diagnostic.message))
+        }
+        Outcome::Clean => "clean".to_owned(),
+        Outcome::Error(_) => "unexpected error".to_owned(),
+        Outcome::Ignored => "ignored".to_owned(),
+        Outcome::NotRun => "not run".to_owned(),
+    }
+}
+
+/// How often each result came up in a seed sweep, e.g.
+/// `aliasing in 24/64 seeds (38%), clean in 40/64 seeds (62%)`.
+pub fn seed_rates(results: &BTreeMap<String, u32>, runs: u32) -> String {
+    if runs == 0 {
+        return "nothing".to_owned();
+    }
+    results
+        .iter()
+        .map(|(result, count)| {
+            let percent = f64::from(*count) * 100.0 / f64::from(runs);
+            format!("{result} in {count}/{runs} seeds ({percent:.0}%)")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A short description of an expectation, for a table cell.
+pub fn expectation_label(expectation: Option<&Expectation>) -> String {
+    match expectation {
+        Some(Expectation::Ub { kind, .. }) => format!("UB ({kind})"),
+        Some(Expectation::Clean) => "clean".to_owned(),
+        None => "-".to_owned(),
+    }
+}
+
+/// A plain-text table with left-aligned columns.
+#[derive(Debug, Clone, Default)]
+pub struct Table {
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: ToString>(headers: impl IntoIterator<Item = S>) -> Table {
+        let mut table = Table::default();
+        table.push(headers);
+        table
+    }
+
+    pub fn push<S: ToString>(&mut self, row: impl IntoIterator<Item = S>) {
+        self.rows
+            .push(row.into_iter().map(|cell| cell.to_string()).collect());
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        for row in &self.rows {
+            let mut line = String::new();
+            for (cell, width) in row.iter().zip(&widths) {
+                line.push_str(&format!("{cell:<width$}  "));
+            }
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::miri::Diagnostic;
+
+    fn ub(message: &str) -> Outcome {
+        Outcome::Ub(Diagnostic {
+            message: message.to_owned(),
+            text: String::new(),
+        })
+    }
+
+    #[test]
+    fn wrong_ub_is_a_regression() {
+        let expected = Expectation::Ub {
+            kind: UbKind::InvalidValue,
+            message: "encountered a null reference".to_owned(),
+        };
+        let right = ub("constructing invalid value of type &u32: encountered a null reference");
+        let wrong = ub("entering unreachable code");
+
+        assert_eq!(Verdict::new(Some(&expected), &right), Verdict::Pass);
+        assert_eq!(Verdict::new(Some(&expected), &wrong), Verdict::Regression);
+        assert_eq!(
+            Verdict::new(Some(&expected), &Outcome::Clean),
+            Verdict::Regression
+        );
+        assert_eq!(
+            Verdict::new(Some(&expected), &Outcome::NotRun),
+            Verdict::Skipped
+        );
+        assert_eq!(Verdict::new(None, &right), Verdict::Undeclared);
+        assert_eq!(
+            Verdict::new(
+                Some(&Expectation::Clean),
+                &Outcome::Error("boom".to_owned())
+            ),
+            Verdict::Regression
+        );
+    }
+
+    #[test]
+    fn combined_verdict_is_the_worse_one() {
+        assert_eq!(Verdict::Pass.and(Verdict::Pass), Verdict::Pass);
+        assert_eq!(Verdict::Pass.and(Verdict::Skipped), Verdict::Skipped);
+        assert_eq!(Verdict::Regression.and(Verdict::Pass), Verdict::Regression);
+        assert_eq!(
+            Verdict::Skipped.and(Verdict::Regression),
+            Verdict::Regression
+        );
+    }
+
+    #[test]
+    fn table_aligns_columns() {
+        let mut table = Table::new(["TEST", "RESULT"]);
+        table.push(["null_reference", "UB detected (invalid-value)"]);
+        table.push(["a", "clean"]);
+        assert_eq!(
+            table.to_string(),
+            "\
+TEST            RESULT
+null_reference  UB detected (invalid-value)
+a               clean
+"
+        );
+    }
+
+    #[test]
+    fn describes_seed_rates() {
+        let results = BTreeMap::from([("aliasing".to_owned(), 24), ("clean".to_owned(), 40)]);
+        assert_eq!(
+            seed_rates(&results, 64),
+            "aliasing in 24/64 seeds (38%), clean in 40/64 seeds (62%)"
+        );
+        assert_eq!(seed_rates(&BTreeMap::new(), 0), "nothing");
+    }
+}
@@ -0,0 +1,666 @@
+//! A static site for GitHub Pages: the README as the index, and each
+//! example's `lib.rs` as a literate page, with the comments as prose, the
+//! code highlighted, Miri's diagnostics shown at the lines they blame, and
+//! the fix shown as a diff.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use pulldown_cmark::{CowStr, Event, Parser, Tag};
+use similar::{ChangeTag, TextDiff};
+
+use crate::example::{Example, Zoo, REPOSITORY};
+use crate::miri::parse_diagnostic;
+use crate::snapshot;
+use crate::source::is_soundness_heading;
+use crate::Result;
+
+/// Where `cargo zoo site` writes the site, relative to the workspace root.
+pub const SITE_DIR: &str = "target/site";
+
+const STYLE: &str = "\
+body { max-width: 52rem; margin: 2rem auto; padding: 0 1rem; font: 16px/1.5 system-ui, sans-serif; color: #222; }
+a { color: #0550ae; }
+code, pre { font: 14px/1.45 ui-monospace, monospace; }
+pre { background: #f6f8fa; padding: 0.5rem 0; overflow-x: auto; border-radius: 4px; }
+pre .line { display: block; padding-right: 1rem; }
+pre .line:target { background: #fff8c5; }
+pre .number { display: inline-block; width: 3.5em; padding-right: 1em; text-align: right; color: #8c959f; text-decoration: none; user-select: none; }
+.meta { color: #57606a; }
+.miri { border-left: 4px solid #cf222e; margin: -0.5rem 0 1rem; padding-left: 0.75rem; }
+.miri pre { background: #fff5f5; padding: 0.5rem; }
+.diff .added { background: #e6ffec; }
+.diff .removed { background: #ffebe9; }
+.diff .skip { color: #8c959f; }
+.k { color: #cf222e; } .s { color: #0a3069; } .n { color: #0550ae; } .c { color: #6e7781; font-style: italic; }
+.a { color: #8250df; } .l { color: #953800; } .m { color: #8250df; } .t { color: #953800; }
+";
+
+/// One file of the site.
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Relative to the site's root.
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// Every page of the site.
+pub fn pages(zoo: &Zoo) -> Result<Vec<Page>> {
+    let mut pages = vec![
+        Page {
+            path: "index.html".into(),
+            contents: document(zoo, "README.md", "The Unsound Zoo")?,
+        },
+        Page {
+            path: "introduction.html".into(),
+            contents: document(zoo, "introduction.md", "Introduction to the Unsound Zoo")?,
+        },
+        Page {
+            path: "style.css".into(),
+            contents: STYLE.to_owned(),
+        },
+    ];
+    // GitHub Pages reads the custom domain from the published site.
+    if let Ok(cname) = std::fs::read_to_string(zoo.root.join("CNAME")) {
+        pages.push(Page {
+            path: "CNAME".into(),
+            contents: cname,
+        });
+    }
+    for example in &zoo.examples {
+        pages.push(Page {
+            path: format!("{}.html", example.name).into(),
+            contents: example_page(example)?,
+        });
+    }
+    Ok(pages)
+}
+
+/// Write the site to `out`, returning how many files it has.
+pub fn write(zoo: &Zoo, out: &Path) -> Result<usize> {
+    let pages = pages(zoo)?;
+    std::fs::create_dir_all(out).map_err(|e| format!("creating {}: {e}", out.display()))?;
+    for page in &pages {
+        let path = out.join(&page.path);
+        std::fs::write(&path, &page.contents)
+            .map_err(|e| format!("writing {}: {e}", path.display()))?;
+    }
+    Ok(pages.len())
+}
+
+fn layout(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<link rel=\"stylesheet\" href=\"style.css\">\n</head>\n<body>\n\
+         <nav><a href=\"index.html\">The Unsound Zoo</a></nav>\n{body}</body>\n</html>\n",
+        escape(&title.replace('`', ""))
+    )
+}
+
+/// A Markdown file at the root, rendered, with its links to the examples and
+/// to the introduction pointing at their pages on the site.
+fn document(zoo: &Zoo, file: &str, title: &str) -> Result<String> {
+    let path = zoo.root.join(file);
+    let text =
+        std::fs::read_to_string(&path).map_err(|e| format!("reading {}: {e}", path.display()))?;
+    let pages: BTreeMap<String, String> = zoo
+        .examples
+        .iter()
+        .map(|example| (example.source_url(), format!("{}.html", example.name)))
+        .chain([("introduction.md".to_owned(), "introduction.html".to_owned())])
+        .collect();
+    let link = |url: &str| -> String {
+        if let Some(page) = pages.get(url) {
+            page.clone()
+        } else if url.contains("://") || url.starts_with('#') {
+            url.to_owned()
+        } else {
+            // Other files in the repository aren't part of the site.
+            format!("{REPOSITORY}/blob/main/{url}")
+        }
+    };
+    Ok(layout(title, &markdown(&text, &link)))
+}
+
+/// Render Markdown, passing link targets through `link`. Any HTML in the
+/// text is shown as text: comments in the examples say things like
+/// `Box<u32>` without backticks.
+fn markdown(text: &str, link: &dyn Fn(&str) -> String) -> String {
+    let events = Parser::new(text).map(|event| match event {
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: CowStr::from(link(&dest_url)),
+            title,
+            id,
+        }),
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        event => event,
+    });
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, events);
+    html
+}
+
+fn example_page(example: &Example) -> Result<String> {
+    let metadata = example
+        .metadata
+        .as_ref()
+        .ok_or_else(|| format!("`{}` has no [package.metadata.zoo] section", example.name))?;
+
+    let mut body = String::new();
+    let _ = writeln!(body, "<h1>{}</h1>", inline_markdown(&metadata.title));
+    let _ = writeln!(
+        body,
+        "<p class=\"meta\">{} &middot; {} &middot; {} &middot; \
+         <a href=\"{}\">the Reference</a> &middot; <a href=\"{}\">source</a></p>",
+        escape(&metadata.id),
+        metadata.ub_class,
+        if metadata.miri_detects {
+            "Miri detects it"
+        } else {
+            "Miri doesn't detect it"
+        },
+        escape(&metadata.reference),
+        escape(&example.source_url()),
+    );
+    body.push_str(&literate(example)?);
+
+    let sound = sound_diff(&example.source.text);
+    if !sound.is_empty() {
+        body.push_str("<h2>The sound version</h2>\n");
+        body.push_str(
+            "<p>With the <code>sound</code> feature enabled, the code changes like this:</p>\n",
+        );
+        body.push_str(&sound);
+    }
+    Ok(layout(&metadata.title, &body))
+}
+
+/// Markdown that should render inside a heading, without a paragraph.
+fn inline_markdown(text: &str) -> String {
+    let html = markdown(text, &str::to_owned);
+    let html = html.trim();
+    let html = html.strip_prefix("<p>").unwrap_or(html);
+    html.strip_suffix("</p>").unwrap_or(html).to_owned()
+}
+
+/// The example's source as alternating prose and code, with the diagnostic
+/// from each test's snapshot after the line Miri blames.
+fn literate(example: &Example) -> Result<String> {
+    let text = &example.source.text;
+    let lines = highlight_lines(text);
+
+    // Anchors for each test, named by its ID.
+    let mut anchors: BTreeMap<usize, String> = BTreeMap::new();
+    let mut diagnostics: BTreeMap<usize, Vec<(String, String)>> = BTreeMap::new();
+    let ids = example.metadata.as_ref().map(|metadata| &metadata.test_ids);
+    let source = format!("/{}/src/lib.rs", example.name);
+    for test in &example.source.tests {
+        if let Some(id) = ids.and_then(|ids| ids.get(&test.path)) {
+            anchors.insert(*test.lines.start(), id.clone());
+        }
+        let path = snapshot::path(example, test);
+        let snapshot = match std::fs::read_to_string(&path) {
+            Ok(snapshot) => snapshot,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("reading {}: {e}", path.display()).into()),
+        };
+        let Some(diagnostic) = parse_diagnostic(&snapshot) else {
+            continue;
+        };
+        let Some(span) = diagnostic
+            .spans(Path::new(""))
+            .into_iter()
+            .find(|span| format!("/{}", span.path).ends_with(&source))
+        else {
+            continue;
+        };
+        diagnostics
+            .entry(span.line)
+            .or_default()
+            .push((test.path.clone(), diagnostic.text));
+    }
+
+    let mut out = String::new();
+    let mut prose: Vec<&str> = Vec::new();
+    let mut code: Vec<usize> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim_start();
+        let comment = ["//!", "///", "//"]
+            .iter()
+            .find_map(|marker| trimmed.strip_prefix(marker));
+        match comment {
+            Some(comment) => {
+                flush_code(&mut out, &mut code, &lines, &anchors, &diagnostics);
+                prose.push(comment.strip_prefix(' ').unwrap_or(comment));
+            }
+            None => {
+                flush_prose(&mut out, &mut prose);
+                code.push(index + 1);
+            }
+        }
+    }
+    flush_code(&mut out, &mut code, &lines, &anchors, &diagnostics);
+    flush_prose(&mut out, &mut prose);
+    Ok(out)
+}
+
+fn flush_prose(out: &mut String, prose: &mut Vec<&str>) {
+    if prose.iter().all(|line| line.trim().is_empty()) {
+        prose.clear();
+        return;
+    }
+    let mut text = prose.join("\n");
+    if is_soundness_heading(&format!("// {}", prose[0])) {
+        let _ = writeln!(
+            out,
+            "<h2>{}</h2>",
+            escape(prose[0].trim().trim_end_matches(':'))
+        );
+        text = prose[1..].join("\n");
+    }
+    out.push_str(&markdown(&text, &str::to_owned));
+    prose.clear();
+}
+
+fn flush_code(
+    out: &mut String,
+    code: &mut Vec<usize>,
+    lines: &[String],
+    anchors: &BTreeMap<usize, String>,
+    diagnostics: &BTreeMap<usize, Vec<(String, String)>>,
+) {
+    // Blank lines around a block of code are just spacing between it and
+    // the prose.
+    while code
+        .first()
+        .is_some_and(|&n| lines[n - 1].trim().is_empty())
+    {
+        code.remove(0);
+    }
+    while code.last().is_some_and(|&n| lines[n - 1].trim().is_empty()) {
+        code.pop();
+    }
+    if code.is_empty() {
+        return;
+    }
+
+    let mut open = false;
+    for &number in code.iter() {
+        if !open {
+            out.push_str("<pre><code>");
+            open = true;
+        }
+        if let Some(id) = anchors.get(&number) {
+            let _ = write!(out, "<a id=\"{}\"></a>", escape(id));
+        }
+        let _ = writeln!(
+            out,
+            "<span class=\"line\" id=\"L{number}\"><a class=\"number\" href=\"#L{number}\">{number}</a>{}</span>",
+            lines[number - 1]
+        );
+        for (test, diagnostic) in diagnostics.get(&number).into_iter().flatten() {
+            if open {
+                out.push_str("</code></pre>\n");
+                open = false;
+            }
+            let _ = writeln!(
+                out,
+                "<div class=\"miri\"><p>Miri, running <code>{}</code>:</p><pre>{}</pre></div>",
+                escape(test),
+                escape(diagnostic)
+            );
+        }
+    }
+    if open {
+        out.push_str("</code></pre>\n");
+    }
+    code.clear();
+}
+
+/// A diff from the code as it builds without the `sound` feature to the code
+/// as it builds with it, as HTML. Empty if the feature changes nothing.
+fn sound_diff(text: &str) -> String {
+    let unsound = configure(text, false);
+    let sound = configure(text, true);
+    let diff = TextDiff::from_lines(&unsound, &sound);
+
+    let mut out = String::new();
+    for (index, group) in diff.grouped_ops(2).iter().enumerate() {
+        if index > 0 {
+            out.push_str("<span class=\"line skip\">…</span>\n");
+        }
+        for op in group {
+            for change in diff.iter_changes(op) {
+                let (class, sign) = match change.tag() {
+                    ChangeTag::Equal => ("line", ' '),
+                    ChangeTag::Delete => ("line removed", '-'),
+                    ChangeTag::Insert => ("line added", '+'),
+                };
+                let line = change.value().trim_end_matches('\n');
+                let _ = writeln!(
+                    out,
+                    "<span class=\"{class}\">{sign} {}</span>",
+                    highlight(line)
+                );
+            }
+        }
+    }
+    if out.is_empty() {
+        return out;
+    }
+    format!("<pre class=\"diff\"><code>{out}</code></pre>\n")
+}
+
+/// The source as it builds with or without the `sound` feature: the code
+/// behind the other configuration removed, and the `cfg` attributes with it.
+fn configure(text: &str, sound: bool) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut keep = vec![true; lines.len()];
+    for (index, line) in lines.iter().enumerate() {
+        let gated_on = match line.trim() {
+            "#[cfg(feature = \"sound\")]" => true,
+            "#[cfg(not(feature = \"sound\"))]" => false,
+            _ => continue,
+        };
+        keep[index] = false;
+        if gated_on != sound {
+            let end = gated_end(&lines, index + 1);
+            for kept in &mut keep[index..=end] {
+                *kept = false;
+            }
+        }
+    }
+    lines
+        .iter()
+        .zip(keep)
+        .filter(|(_, keep)| *keep)
+        .map(|(line, _)| format!("{line}\n"))
+        .collect()
+}
+
+/// The last line of the item or statement that starts at `start`, found by
+/// matching brackets: it ends with a `;` or a closing brace at the outer
+/// level (unless an `else` follows on the next line), or just before the
+/// block around it closes.
+fn gated_end(lines: &[&str], start: usize) -> usize {
+    let mut depth = 0i32;
+    let mut closed_at = None;
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        let code = line.split_once("//").map_or(*line, |(code, _)| code);
+        if let Some(closed) = closed_at {
+            if code.trim_start().starts_with("else") {
+                closed_at = None;
+            } else if !code.trim().is_empty() {
+                return closed;
+            }
+        }
+        let mut in_string = false;
+        let mut chars = code.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if in_string => {
+                    chars.next();
+                }
+                '"' => in_string = !in_string,
+                _ if in_string => {}
+                '(' | '[' | '{' => {
+                    depth += 1;
+                    closed_at = None;
+                }
+                ')' | ']' => depth -= 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        closed_at = Some(index);
+                    }
+                }
+                ';' if depth == 0 => return index,
+                _ => {}
+            }
+            if depth < 0 {
+                return index - 1;
+            }
+        }
+    }
+    closed_at.unwrap_or(lines.len() - 1)
+}
+
+/// The source, highlighted, one string of HTML per line.
+fn highlight_lines(text: &str) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for (class, token) in tokens(text) {
+        for (index, part) in token.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(String::new());
+            }
+            push_token(lines.last_mut().unwrap(), class, part);
+        }
+    }
+    if text.ends_with('\n') {
+        lines.pop();
+    }
+    lines
+}
+
+fn highlight(text: &str) -> String {
+    let mut out = String::new();
+    for (class, token) in tokens(text) {
+        push_token(&mut out, class, token);
+    }
+    out
+}
+
+fn push_token(out: &mut String, class: Option<&str>, text: &str) {
+    match class {
+        Some(class) if !text.is_empty() => {
+            let _ = write!(out, "<span class=\"{class}\">{}</span>", escape(text));
+        }
+        _ => out.push_str(&escape(text)),
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Split Rust source into tokens, each with the CSS class to show it in:
+/// `k`eywords, `s`trings, `n`umbers, `c`omments, `a`ttributes, `l`ifetimes,
+/// `m`acros and `t`ypes. This is only for looks; it doesn't need to be a
+/// real lexer.
+fn tokens(text: &str) -> Vec<(Option<&'static str>, &str)> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &text[i..];
+        let (class, len) = if rest.starts_with("//") {
+            (Some("c"), rest.find('\n').unwrap_or(rest.len()))
+        } else if rest.starts_with("/*") {
+            (Some("c"), rest.find("*/").map_or(rest.len(), |end| end + 2))
+        } else if rest.starts_with("#[") || rest.starts_with("#![") {
+            (Some("a"), bracketed(rest))
+        } else if let Some(len) = string_literal(rest) {
+            (Some("s"), len)
+        } else if rest.starts_with('\'') {
+            lifetime_or_char(rest)
+        } else if bytes[i].is_ascii_digit() {
+            let len = rest
+                .char_indices()
+                .find(|&(n, c)| {
+                    !(c.is_ascii_alphanumeric()
+                        || c == '_'
+                        || (c == '.' && rest[n + 1..].starts_with(|c: char| c.is_ascii_digit())))
+                })
+                .map_or(rest.len(), |(n, _)| n);
+            (Some("n"), len)
+        } else if rest.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let word = &rest[..len];
+            if rest[len..].starts_with('!') && !rest[len..].starts_with("!=") {
+                (Some("m"), len + 1)
+            } else if KEYWORDS.contains(&word) {
+                (Some("k"), len)
+            } else if word.starts_with(|c: char| c.is_uppercase()) {
+                (Some("t"), len)
+            } else {
+                (None, len)
+            }
+        } else {
+            (None, rest.chars().next().unwrap().len_utf8())
+        };
+        tokens.push((class, &text[i..i + len]));
+        i += len;
+    }
+    tokens
+}
+
+fn bracketed(text: &str) -> usize {
+    let mut depth = 0;
+    for (n, c) in text.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return n + 1;
+                }
+            }
+            '\n' => return n,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// The length of the string literal `text` starts with, if it does.
+fn string_literal(text: &str) -> Option<usize> {
+    let prefix = text.len() - text.trim_start_matches(['b', 'r', 'c']).len();
+    let after_prefix = &text[prefix..];
+    if prefix > 2 {
+        return None;
+    }
+    let raw = text[..prefix].contains('r');
+    if raw {
+        let hashes = after_prefix.len() - after_prefix.trim_start_matches('#').len();
+        let body = after_prefix[hashes..].strip_prefix('"')?;
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let end = body.find(&closing)?;
+        return Some(prefix + hashes + 1 + end + closing.len());
+    }
+    let body = after_prefix.strip_prefix('"')?;
+    let mut chars = body.char_indices();
+    while let Some((n, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '"' => return Some(prefix + 1 + n + 1),
+            _ => {}
+        }
+    }
+    Some(text.len())
+}
+
+fn lifetime_or_char(text: &str) -> (Option<&'static str>, usize) {
+    let mut chars = text.char_indices().skip(1);
+    match (chars.next(), chars.next()) {
+        (Some((_, '\\')), _) => {
+            let end = text.get(3..).and_then(|rest| rest.find('\''));
+            (Some("s"), end.map_or(text.len(), |n| n + 4))
+        }
+        (Some(_), Some((n, '\''))) => (Some("s"), n + 1),
+        _ => {
+            let len = text[1..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .map_or(text.len(), |n| n + 1);
+            (Some("l"), len)
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = r#"#[test]
+fn example() {
+    #[cfg(not(feature = "sound"))]
+    let x = unsafe {
+        transmute(1u8)
+    };
+    #[cfg(feature = "sound")]
+    let x = true;
+
+    #[cfg(feature = "sound")]
+    if x {
+        println!("{x}");
+    } else {
+        panic!();
+    }
+    #[cfg(not(feature = "sound"))]
+    x
+}
+"#;
+
+    #[test]
+    fn configures_each_version() {
+        assert_eq!(
+            configure(SOURCE, false),
+            "#[test]\nfn example() {\n    let x = unsafe {\n        transmute(1u8)\n    };\n\n    x\n}\n"
+        );
+        assert_eq!(
+            configure(SOURCE, true),
+            "#[test]\nfn example() {\n    let x = true;\n\n    if x {\n        println!(\"{x}\");\n    \
+             } else {\n        panic!();\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn highlights_rust() {
+        assert_eq!(
+            highlight(r#"let s: &'static str = "a\"b"; // done"#),
+            "<span class=\"k\">let</span> s: &amp;<span class=\"l\">'static</span> str = \
+             <span class=\"s\">&quot;a\\&quot;b&quot;</span>; <span class=\"c\">// done</span>"
+        );
+        assert_eq!(
+            highlight("assert_eq!(x, '\\'', Box::new(0x1f));"),
+            "<span class=\"m\">assert_eq!</span>(x, <span class=\"s\">'\\''</span>, \
+             <span class=\"t\">Box</span>::new(<span class=\"n\">0x1f</span>));"
+        );
+    }
+
+    #[test]
+    fn shows_diagnostics_in_place() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+        let zoo = Zoo::load(root).unwrap();
+        let example = zoo.example("null_reference").unwrap();
+        let page = example_page(example).unwrap();
+
+        let line = page.find("id=\"L12\"").unwrap();
+        let miri = page.find("<div class=\"miri\">").unwrap();
+        let next = page.find("id=\"L15\"").unwrap();
+        assert!(line < miri && miri < next);
+        assert!(page.contains("<h2>To make this code sound</h2>"));
+        assert!(page.contains("<span class=\"line added\">"));
+    }
+}
@@ -0,0 +1,285 @@
+//! `.stderr` snapshots of what Miri says about each test.
+//!
+//! Every test that makes Miri complain has its output stored next to the
+//! example, in `snapshots/<test>.stderr`, so that a change in a diagnostic
+//! shows up in review. The output is normalized first: anything that depends
+//! on where the workspace lives, on the build, or on the exact toolchain's
+//! standard library is replaced with a placeholder.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use similar::TextDiff;
+
+use crate::example::Example;
+use crate::source::TestFn;
+use crate::Result;
+
+/// The directory, inside each example, holding its snapshots.
+pub const SNAPSHOT_DIR: &str = "snapshots";
+
+/// A test's stored snapshot, and what it ran as this time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub path: PathBuf,
+    /// The stored snapshot, if there is one.
+    pub expected: Option<String>,
+    /// The normalized output of this run, if there was any.
+    pub actual: Option<String>,
+}
+
+/// How a snapshot compares with the stored one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Unchanged,
+    /// The output differs from the stored snapshot.
+    Changed,
+    /// There is output, but no stored snapshot.
+    New,
+    /// There is a stored snapshot, but no output (or no test).
+    Stale,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Unchanged => "ok",
+            Status::Changed => "CHANGED",
+            Status::New => "NEW",
+            Status::Stale => "STALE",
+        })
+    }
+}
+
+impl Snapshot {
+    /// Compare a test's stderr with its stored snapshot.
+    pub fn new(root: &Path, example: &Example, test: &TestFn, stderr: &str) -> Result<Snapshot> {
+        let path = path(example, test);
+        let actual = normalize(stderr, root);
+        Ok(Snapshot {
+            expected: read(&path)?,
+            actual: (!actual.is_empty()).then_some(actual),
+            path,
+        })
+    }
+
+    pub fn status(&self) -> Status {
+        match (&self.expected, &self.actual) {
+            (expected, actual) if expected == actual => Status::Unchanged,
+            (None, Some(_)) => Status::New,
+            (Some(_), None) => Status::Stale,
+            _ => Status::Changed,
+        }
+    }
+
+    /// A unified diff from the stored snapshot to the new output.
+    pub fn diff(&self) -> String {
+        let expected = self.expected.as_deref().unwrap_or_default();
+        let actual = self.actual.as_deref().unwrap_or_default();
+        TextDiff::from_lines(expected, actual)
+            .unified_diff()
+            .header("expected", "actual")
+            .to_string()
+    }
+
+    /// Make the new output the stored snapshot.
+    pub fn bless(&self) -> Result<()> {
+        let write = |path: &Path| match &self.actual {
+            Some(actual) => {
+                std::fs::create_dir_all(path.parent().unwrap())?;
+                std::fs::write(path, actual)
+            }
+            None => std::fs::remove_file(path),
+        };
+        write(&self.path).map_err(|e| format!("blessing {}: {e}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Where a test's snapshot is stored.
+pub fn path(example: &Example, test: &TestFn) -> PathBuf {
+    example
+        .dir
+        .join(SNAPSHOT_DIR)
+        .join(format!("{}.stderr", test.path.replace("::", ".")))
+}
+
+/// Stored snapshots that don't belong to any of the example's tests.
+pub fn orphans(example: &Example) -> Result<Vec<Snapshot>> {
+    let dir = example.dir.join(SNAPSHOT_DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let owned: Vec<PathBuf> = example
+        .source
+        .tests
+        .iter()
+        .map(|test| path(example, test))
+        .collect();
+
+    let mut orphans = Vec::new();
+    for entry in std::fs::read_dir(&dir).map_err(|e| format!("reading {}: {e}", dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "stderr") && !owned.contains(&path) {
+            orphans.push(Snapshot {
+                expected: read(&path)?,
+                actual: None,
+                path,
+            });
+        }
+    }
+    orphans.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(orphans)
+}
+
+fn read(path: &Path) -> Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("reading {}: {e}", path.display()).into()),
+    }
+}
+
+/// Reduce the stderr of `cargo miri test` to the part worth keeping: what
+/// Miri itself said, minus anything specific to this machine or build.
+pub fn normalize(stderr: &str, root: &Path) -> String {
+    static RULES: LazyLock<Vec<(Regex, &str)>> = LazyLock::new(|| {
+        [
+            // The standard library's sources, wherever rustup put them...
+            (r"\S*/lib/rustlib/src/rust/library/", "$$RUSTLIB/"),
+            // ...and line numbers in them, which change with every nightly.
+            (r"(\$RUSTLIB/\S+\.rs):\d+:\d+(: \d+:\d+)?", "$1:LL:CC"),
+            // Build hashes in artifact names.
+            (r"-[0-9a-f]{16}\b", "-HASH"),
+            // Miri's allocation ids and borrow tags, which shift whenever
+            // the code (or the standard library) allocates a little more.
+            (r"\balloc\d+\b", "ALLOC"),
+            (r"<\d+>", "<TAG>"),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+        .collect()
+    });
+    static CARGO_STATUS: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^ +(Blocking|Checking|Compiling|Finished|Preparing|Running) ").unwrap()
+    });
+    static GUTTER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^\d+( +\|)").unwrap());
+
+    let root = format!("{}/", root.display());
+    let mut lines = Vec::new();
+    let mut in_std_snippet = false;
+    for line in stderr.lines() {
+        // Cargo's own complaint, and everything after it, is about the test
+        // binary failing, which we already know.
+        if line.starts_with("error: test failed, to rerun") {
+            break;
+        }
+        if CARGO_STATUS.is_match(line) {
+            continue;
+        }
+
+        let mut line = line.replace(&root, "$DIR/");
+        for (pattern, replacement) in RULES.iter() {
+            line = pattern.replace_all(&line, *replacement).into_owned();
+        }
+        if line.trim_start().starts_with("-->") {
+            in_std_snippet = line.contains("$RUSTLIB/");
+        } else if in_std_snippet {
+            line = GUTTER.replace(&line, "LL$1").into_owned();
+        }
+        lines.push(line.trim_end().to_owned());
+    }
+
+    let text = lines.join("\n");
+    let text = text.trim();
+    if text.is_empty() {
+        String::new()
+    } else {
+        format!("{text}\n")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const STDERR: &str = "   Compiling buggy_drain v0.1.0 (/home/me/zoo/unsound_examples/buggy_drain)
+    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.02s
+     Running unittests src/lib.rs (target/miri/x86_64-unknown-linux-gnu/debug/deps/buggy_drain-8b3d64f5264cbd0c)
+error: Undefined Behavior: not granting access to tag <135607> because that would remove [SharedReadOnly for <133761>] in alloc1234
+   --> /home/me/zoo/unsound_examples/buggy_drain/src/lib.rs:189:5
+    |
+189 |     assert_eq!(2, *v[0]);
+    |     ^^^^^^^^^^^^^^^^^^^^ Undefined Behavior occurred here
+    |
+    = note: stack backtrace:
+            0: fails_when_drain_leaked
+                at /home/me/.rustup/toolchains/nightly-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/core/src/macros/mod.rs:44:24: 44:31
+note: inside `std::ptr::read::<u8>`
+   --> /home/me/.rustup/toolchains/nightly-x86_64-unknown-linux-gnu/lib/rustlib/src/rust/library/core/src/ptr/mod.rs:1712:9
+    |
+1712 |         crate::intrinsics::read_via_copy(src)
+    |         ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+error: aborting due to 1 previous error
+
+error: test failed, to rerun pass `-p buggy_drain --lib`
+
+Caused by:
+  process didn't exit successfully: `cargo-miri runner buggy_drain-8b3d64f5264cbd0c` (exit status: 1)
+";
+
+    #[test]
+    fn normalizes_machine_specific_output() {
+        let normalized = normalize(STDERR, Path::new("/home/me/zoo"));
+        assert_eq!(
+            normalized,
+            "\
+error: Undefined Behavior: not granting access to tag <TAG> because that would remove [SharedReadOnly for <TAG>] in ALLOC
+   --> $DIR/unsound_examples/buggy_drain/src/lib.rs:189:5
+    |
+189 |     assert_eq!(2, *v[0]);
+    |     ^^^^^^^^^^^^^^^^^^^^ Undefined Behavior occurred here
+    |
+    = note: stack backtrace:
+            0: fails_when_drain_leaked
+                at $RUSTLIB/core/src/macros/mod.rs:LL:CC
+note: inside `std::ptr::read::<u8>`
+   --> $RUSTLIB/core/src/ptr/mod.rs:LL:CC
+    |
+LL |         crate::intrinsics::read_via_copy(src)
+    |         ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+
+error: aborting due to 1 previous error
+"
+        );
+    }
+
+    #[test]
+    fn clean_runs_have_no_output() {
+        let stderr = "    Finished `test` profile [unoptimized + debuginfo] target(s) in 0.02s\n     Running unittests src/lib.rs (target/miri/x/debug/deps/a-0123456789abcdef)\n";
+        assert_eq!(normalize(stderr, Path::new("/zoo")), "");
+    }
+
+    #[test]
+    fn compares_with_the_stored_snapshot() {
+        let snapshot = |expected: Option<&str>, actual: Option<&str>| Snapshot {
+            path: PathBuf::from("a.stderr"),
+            expected: expected.map(str::to_owned),
+            actual: actual.map(str::to_owned),
+        };
+        assert_eq!(snapshot(None, None).status(), Status::Unchanged);
+        assert_eq!(
+            snapshot(Some("a\n"), Some("a\n")).status(),
+            Status::Unchanged
+        );
+        assert_eq!(snapshot(Some("a\n"), Some("b\n")).status(), Status::Changed);
+        assert_eq!(snapshot(None, Some("b\n")).status(), Status::New);
+        assert_eq!(snapshot(Some("a\n"), None).status(), Status::Stale);
+        assert!(snapshot(Some("a\n"), Some("b\n"))
+            .diff()
+            .contains("-a\n+b\n"));
+    }
+}
@@ -0,0 +1,316 @@
+//! Reading the tests, and what they expect, out of an example's `lib.rs`.
+
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+use syn::spanned::Spanned;
+use syn::{Attribute, Item, LitStr};
+use zoo_harness::{Expectation, UbKind};
+
+use crate::Result;
+
+/// One `#[test]` function in an example.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFn {
+    /// The name libtest knows the test by, e.g. `test::bad_lifetime`.
+    pub path: String,
+    /// The function name, e.g. `bad_lifetime`.
+    pub name: String,
+    /// The lines the function occupies, including its attributes.
+    pub lines: RangeInclusive<usize>,
+    /// What the test declared with `#[expect_ub]` or `#[expect_clean]`.
+    pub expectation: Option<Expectation>,
+    /// The test is marked `#[ignore]` everywhere, not just outside Miri.
+    pub ignored: bool,
+}
+
+/// A parsed example source file.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub path: PathBuf,
+    pub text: String,
+    pub tests: Vec<TestFn>,
+}
+
+impl SourceFile {
+    pub fn read(path: &Path) -> Result<SourceFile> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("reading {}: {e}", path.display()))?;
+        SourceFile::parse(path, text)
+    }
+
+    pub fn parse(path: &Path, text: String) -> Result<SourceFile> {
+        let file =
+            syn::parse_file(&text).map_err(|e| format!("parsing {}: {e}", path.display()))?;
+        let mut tests = Vec::new();
+        collect_tests(&file.items, &mut Vec::new(), &mut tests)?;
+
+        Ok(SourceFile {
+            path: path.to_owned(),
+            text,
+            tests,
+        })
+    }
+
+    /// Find a test by its full libtest path or by its bare function name.
+    pub fn test(&self, name: &str) -> Option<&TestFn> {
+        self.tests
+            .iter()
+            .find(|test| test.path == name)
+            .or_else(|| self.tests.iter().find(|test| test.name == name))
+    }
+
+    /// The given lines of the file (1-based, inclusive).
+    pub fn lines(&self, lines: RangeInclusive<usize>) -> Vec<&str> {
+        self.text
+            .lines()
+            .skip(lines.start() - 1)
+            .take(lines.end() + 1 - lines.start())
+            .collect()
+    }
+
+    /// The test's source, along with the comment block directly above it.
+    pub fn excerpt(&self, test: &TestFn) -> RangeInclusive<usize> {
+        let all: Vec<&str> = self.text.lines().collect();
+        let mut start = *test.lines.start();
+        while start > 1 && all[start - 2].trim_start().starts_with("//") {
+            start -= 1;
+        }
+        start..=*test.lines.end()
+    }
+
+    /// The "To make this code sound" notes that apply to a test, without
+    /// their comment markers.
+    ///
+    /// Some examples explain the fix inside each test, some have a single
+    /// section for the whole file, and some have one section per module; we
+    /// pick the first section after the test, falling back to the last one
+    /// before it.
+    pub fn soundness_notes(&self, test: &TestFn) -> Option<String> {
+        let all: Vec<&str> = self.text.lines().collect();
+        let headings: Vec<usize> = all
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| is_soundness_heading(line))
+            .map(|(index, _)| index + 1)
+            .collect();
+
+        if headings.iter().any(|line| test.lines.contains(line)) {
+            // The notes are already part of the test's own source.
+            return None;
+        }
+        let heading = headings
+            .iter()
+            .find(|&&line| line > *test.lines.end())
+            .or_else(|| {
+                headings
+                    .iter()
+                    .rev()
+                    .find(|&&line| line < *test.lines.start())
+            })?;
+
+        let notes: Vec<&str> = all[*heading..]
+            .iter()
+            .map_while(|line| line.trim_start().strip_prefix("//"))
+            .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end())
+            .collect();
+        let notes = notes.join("\n").trim().to_owned();
+        (!notes.is_empty()).then_some(notes)
+    }
+}
+
+/// Does this line start a "To make this code sound" section?
+pub fn is_soundness_heading(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("//") && line.contains("make this code sound")
+}
+
+fn collect_tests(items: &[Item], module: &mut Vec<String>, tests: &mut Vec<TestFn>) -> Result<()> {
+    for item in items {
+        match item {
+            Item::Fn(function) if has_attr(&function.attrs, "test") => {
+                let name = function.sig.ident.to_string();
+                let path = module
+                    .iter()
+                    .chain(std::iter::once(&name))
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("::");
+                let start = function
+                    .attrs
+                    .first()
+                    .map_or(function.span(), |attr| attr.span())
+                    .start()
+                    .line;
+                let end = function.block.span().end().line;
+
+                tests.push(TestFn {
+                    path,
+                    name,
+                    lines: start..=end,
+                    expectation: expectation(&function.attrs)?,
+                    ignored: has_attr(&function.attrs, "ignore"),
+                });
+            }
+            // Loom's models are only built with `--cfg loom`, and aren't Miri's
+            // to run.
+            Item::Mod(module_item) if is_loom_only(&module_item.attrs) => {}
+            Item::Mod(module_item) => {
+                if let Some((_, items)) = &module_item.content {
+                    module.push(module_item.ident.to_string());
+                    collect_tests(items, module, tests)?;
+                    module.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Does the attribute list contain `#[name]`, ignoring any path prefix?
+fn has_attr(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr_is(attr, name))
+}
+
+/// Is the item gated on `#[cfg(loom)]`, or `#[cfg(all(test, loom))]`?
+fn is_loom_only(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let Ok(list) = attr.meta.require_list() else {
+            return false;
+        };
+        let words: Vec<String> = list
+            .tokens
+            .to_string()
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .map(str::to_owned)
+            .collect();
+        attr.path().is_ident("cfg")
+            && words.iter().any(|word| word == "loom")
+            && !words.iter().any(|word| word == "not" || word == "any")
+    })
+}
+
+fn attr_is(attr: &Attribute, name: &str) -> bool {
+    attr.path()
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == name)
+}
+
+fn expectation(attrs: &[Attribute]) -> Result<Option<Expectation>> {
+    for attr in attrs {
+        if attr_is(attr, "expect_clean") {
+            return Ok(Some(Expectation::Clean));
+        }
+        if attr_is(attr, "expect_ub") {
+            let mut kind = None;
+            let mut message = None;
+            attr.parse_nested_meta(|meta| {
+                let value: LitStr = meta.value()?.parse()?;
+                if meta.path.is_ident("kind") {
+                    kind = Some(value.value());
+                } else if meta.path.is_ident("message") {
+                    message = Some(value.value());
+                }
+                Ok(())
+            })?;
+            let kind: UbKind = kind.ok_or("expect_ub without a kind")?.parse()?;
+            let message = message.ok_or("expect_ub without a message")?;
+            return Ok(Some(Expectation::Ub { kind, message }));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = r#"#[zoo_harness::expect_ub(kind = "invalid-value", message = "a null reference")]
+#[test]
+fn top_level() {
+    // To make this code sound:
+    //
+    // Don't.
+}
+
+#[cfg(test)]
+mod test {
+    // Comment about the test.
+    #[zoo_harness::expect_clean]
+    #[test]
+    fn nested() {}
+
+    #[test]
+    #[ignore = "segfaults"]
+    fn ignored() {}
+
+    fn not_a_test() {}
+
+    // To make this code sound:
+    //
+    // Use the safe API.
+    //
+}
+
+#[cfg(all(test, loom))]
+mod loom_model {
+    #[test]
+    fn modeled() {}
+}
+"#;
+
+    fn parse() -> SourceFile {
+        SourceFile::parse(Path::new("lib.rs"), SOURCE.to_owned()).unwrap()
+    }
+
+    #[test]
+    fn finds_tests_in_modules() {
+        let source = parse();
+        let names: Vec<_> = source.tests.iter().map(|test| test.path.as_str()).collect();
+        assert_eq!(names, ["top_level", "test::nested", "test::ignored"]);
+        assert_eq!(source.tests[0].lines, 1..=7);
+        assert!(source.tests[2].ignored);
+        assert_eq!(source.test("nested"), Some(&source.tests[1]));
+    }
+
+    #[test]
+    fn reads_expectations() {
+        let source = parse();
+        assert_eq!(
+            source.tests[0].expectation,
+            Some(Expectation::Ub {
+                kind: UbKind::InvalidValue,
+                message: "a null reference".to_owned(),
+            })
+        );
+        assert_eq!(source.tests[1].expectation, Some(Expectation::Clean));
+        assert_eq!(source.tests[2].expectation, None);
+    }
+
+    #[test]
+    fn excerpt_includes_leading_comments() {
+        let source = parse();
+        let nested = &source.tests[1];
+        assert_eq!(
+            source.lines(source.excerpt(nested)),
+            [
+                "    // Comment about the test.",
+                "    #[zoo_harness::expect_clean]",
+                "    #[test]",
+                "    fn nested() {}",
+            ]
+        );
+    }
+
+    #[test]
+    fn picks_the_nearest_soundness_notes() {
+        let source = parse();
+        assert_eq!(source.soundness_notes(&source.tests[0]), None);
+        assert_eq!(
+            source.soundness_notes(&source.tests[1]).as_deref(),
+            Some("Use the safe API.")
+        );
+    }
+}
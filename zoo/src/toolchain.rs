@@ -0,0 +1,380 @@
+//! What Miri finds across nightlies: which ones are installed, and where
+//! between two of them a test's outcome changed.
+//!
+//! Miri gets better at noticing undefined behavior (and occasionally stops
+//! noticing some), so "Miri doesn't catch this" is only ever true of some
+//! range of nightlies. Results are recorded by the date of the commit each
+//! nightly was built from, since a bare `nightly` names a different
+//! toolchain after every `rustup update`.
+
+use std::collections::BTreeMap;
+use std::process::Command;
+
+use crate::model::Detection;
+use crate::Result;
+
+/// A nightly toolchain with Miri installed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nightly {
+    /// The name rustup knows it by, e.g. `nightly-2026-09-14-x86_64-unknown-linux-gnu`.
+    pub name: String,
+    /// The date of the commit it was built from, as `rustc -vV` reports it,
+    /// e.g. `2026-09-13`.
+    pub date: String,
+}
+
+/// A change in a test's outcome between two nightlies, with no result
+/// recorded for any nightly in between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub before: String,
+    pub after: String,
+    pub from: Detection,
+    pub to: Detection,
+}
+
+/// Every installed nightly that has Miri, oldest first. Toolchains built
+/// from the same commit, like `nightly` and the dated toolchain it was
+/// installed as, are only listed once.
+pub fn installed() -> Result<Vec<Nightly>> {
+    let output = Command::new("rustup")
+        .args(["toolchain", "list"])
+        .output()
+        .map_err(|e| format!("running rustup: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "rustup toolchain list failed:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+    let list = String::from_utf8_lossy(&output.stdout);
+    let names: Vec<String> = nightly_names(&list).map(str::to_owned).collect();
+    resolve(&names)
+}
+
+/// Look up each of the named toolchains, oldest first, leaving out the ones
+/// without Miri.
+pub fn resolve(names: &[String]) -> Result<Vec<Nightly>> {
+    let mut nightlies = Vec::new();
+    for name in names {
+        if let Some(nightly) = nightly(name)? {
+            nightlies.push(nightly);
+        }
+    }
+    // Prefer `nightly-2026-09-14` to a plain `nightly` built from the same
+    // commit, since it's the name that can be installed again later.
+    nightlies.sort_by(|a, b| a.date.cmp(&b.date).then(a.name.cmp(&b.name)));
+    nightlies.dedup_by(|a, b| a.date == b.date);
+    Ok(nightlies)
+}
+
+/// Look up a toolchain's commit date, if it has Miri.
+fn nightly(name: &str) -> Result<Option<Nightly>> {
+    let toolchain = format!("+{name}");
+    let miri = Command::new("cargo")
+        .env_remove("RUSTUP_TOOLCHAIN")
+        .args([toolchain.as_str(), "miri", "--version"])
+        .output()
+        .map_err(|e| format!("running cargo {toolchain} miri: {e}"))?;
+    if !miri.status.success() {
+        return Ok(None);
+    }
+
+    let output = Command::new("rustc")
+        .env_remove("RUSTUP_TOOLCHAIN")
+        .args([toolchain.as_str(), "-vV"])
+        .output()
+        .map_err(|e| format!("running rustc {toolchain}: {e}"))?;
+    let version = String::from_utf8_lossy(&output.stdout);
+    let date = commit_date(&version)
+        .ok_or_else(|| format!("`rustc {toolchain} -vV` doesn't say when it was built"))?;
+    Ok(Some(Nightly {
+        name: name.to_owned(),
+        date: date.to_owned(),
+    }))
+}
+
+/// The nightly toolchains in the output of `rustup toolchain list`.
+fn nightly_names(list: &str) -> impl Iterator<Item = &str> {
+    list.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| name.starts_with("nightly"))
+}
+
+/// The `commit-date` in the output of `rustc -vV`.
+fn commit_date(version: &str) -> Option<&str> {
+    version
+        .lines()
+        .find_map(|line| line.strip_prefix("commit-date: "))
+        .map(str::trim)
+        .filter(|date| *date != "unknown")
+}
+
+/// What one test did under a range of nightlies, as found by [`drift`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Drift {
+    /// What each nightly that was run found, by index, or `None` if it
+    /// couldn't build the workspace.
+    pub outcomes: BTreeMap<usize, Option<Detection>>,
+    /// The oldest nightly that could build the workspace, by index.
+    pub oldest: Option<usize>,
+    /// Where the detection changed between the oldest and the newest
+    /// nightly that could build the workspace: the last nightly with the
+    /// old detection and the first with the new one, by index.
+    pub change: Option<(usize, usize)>,
+}
+
+impl Drift {
+    /// The change this run found, between two of the `nightlies` it ran.
+    pub fn change(&self, nightlies: &[Nightly]) -> Option<Change> {
+        let (before, after) = self.change?;
+        Some(Change {
+            before: nightlies[before].date.clone(),
+            after: nightlies[after].date.clone(),
+            from: self.outcomes[&before]?,
+            to: self.outcomes[&after]?,
+        })
+    }
+
+    /// What the oldest and the newest of `count` nightlies found, for a
+    /// table: just the one if there's only one. A nightly that wasn't run,
+    /// because the newest one couldn't build the workspace, is `not run`.
+    pub fn cells(&self, count: usize) -> Vec<String> {
+        let ends = if count > 1 {
+            vec![0, count - 1]
+        } else {
+            vec![0]
+        };
+        ends.into_iter()
+            .map(|index| match self.outcomes.get(&index) {
+                Some(Some(detection)) => detection.to_string(),
+                Some(None) => "can't build".to_owned(),
+                None => "not run".to_owned(),
+            })
+            .collect()
+    }
+}
+
+/// Run a test under the oldest and newest of `count` nightlies, in order,
+/// and if they disagree, bisect to where its detection changed. `run` gives
+/// what a nightly found, or `None` if it couldn't build the workspace; each
+/// nightly is run at most once.
+///
+/// Nightlies too old to build the workspace (say, to read its `Cargo.lock`)
+/// aren't a change in what Miri finds, so the comparison starts from the
+/// oldest nightly that can, and any others that can't are skipped.
+pub fn drift(
+    count: usize,
+    mut run: impl FnMut(usize) -> Result<Option<Detection>>,
+) -> Result<Drift> {
+    let mut outcomes = BTreeMap::new();
+    let mut outcome = |index: usize| -> Result<Option<Detection>> {
+        if let Some(outcome) = outcomes.get(&index) {
+            return Ok(*outcome);
+        }
+        let outcome = run(index)?;
+        outcomes.insert(index, outcome);
+        Ok(outcome)
+    };
+
+    let newest = count - 1;
+    let (oldest, change) = match outcome(newest)? {
+        None => (None, None),
+        Some(last) => {
+            let oldest = match outcome(0)? {
+                Some(_) => 0,
+                None => bisect(count, &false, |index| Ok(Some(outcome(index)?.is_some())))?.1,
+            };
+            let first = outcome(oldest)?.expect("the oldest nightly that builds has a result");
+            let change = if first == last {
+                None
+            } else {
+                let (before, after) =
+                    bisect(newest - oldest + 1, &first, |index| outcome(oldest + index))?;
+                Some((oldest + before, oldest + after))
+            };
+            (Some(oldest), change)
+        }
+    };
+    Ok(Drift {
+        outcomes,
+        oldest,
+        change,
+    })
+}
+
+/// Find where the outcome changes in a sequence of `count` toolchains, in
+/// order, whose first and last outcomes differ: the index of the last
+/// toolchain whose outcome is `first`, and of the next one whose outcome
+/// isn't. `outcome` gives `None` for toolchains that can't be tested, which
+/// are skipped, so there may be some of those in between.
+///
+/// This assumes the outcome changed only once, and calls `outcome` on about
+/// log2(`count`) of the toolchains in between.
+pub fn bisect<T: PartialEq>(
+    count: usize,
+    first: &T,
+    mut outcome: impl FnMut(usize) -> Result<Option<T>>,
+) -> Result<(usize, usize)> {
+    let (mut before, mut after) = (0, count - 1);
+    while after - before > 1 {
+        let middle = before + (after - before) / 2;
+        // The closest toolchain to the middle that can be tested, looking
+        // up first.
+        let mut tested = None;
+        for index in (middle..after).chain((before + 1..middle).rev()) {
+            if let Some(result) = outcome(index)? {
+                tested = Some((index, result));
+                break;
+            }
+        }
+        match tested {
+            Some((index, result)) if result == *first => before = index,
+            Some((index, _)) => after = index,
+            None => break,
+        }
+    }
+    Ok((before, after))
+}
+
+/// Every change in a test's recorded outcomes (by commit date), oldest
+/// first.
+pub fn changes(history: &BTreeMap<String, Detection>) -> Vec<Change> {
+    history
+        .iter()
+        .zip(history.iter().skip(1))
+        .filter(|((_, from), (_, to))| from != to)
+        .map(|((before, from), (after, to))| Change {
+            before: before.clone(),
+            after: after.clone(),
+            from: *from,
+            to: *to,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use zoo_harness::UbKind;
+
+    use super::*;
+
+    #[test]
+    fn reads_toolchains() {
+        let list = "\
+stable-x86_64-unknown-linux-gnu (default)
+nightly-2025-03-01-x86_64-unknown-linux-gnu
+nightly-x86_64-unknown-linux-gnu (active)
+";
+        assert_eq!(
+            nightly_names(list).collect::<Vec<_>>(),
+            [
+                "nightly-2025-03-01-x86_64-unknown-linux-gnu",
+                "nightly-x86_64-unknown-linux-gnu"
+            ]
+        );
+
+        let version = "\
+rustc 1.97.0-nightly (0123456789 2026-09-13)
+binary: rustc
+commit-hash: 0123456789abcdef
+commit-date: 2026-09-13
+host: x86_64-unknown-linux-gnu
+";
+        assert_eq!(commit_date(version), Some("2026-09-13"));
+        assert_eq!(commit_date("commit-date: unknown\n"), None);
+    }
+
+    #[test]
+    fn bisects_to_the_first_change() {
+        let outcomes = [0, 0, 0, 0, 1, 1, 1, 1, 1, 1];
+        let mut tried = Vec::new();
+        let pair = bisect(outcomes.len(), &0, |index| {
+            tried.push(index);
+            Ok(Some(outcomes[index]))
+        })
+        .unwrap();
+        assert_eq!(pair, (3, 4));
+        assert!(tried.len() <= 4, "{tried:?}");
+
+        assert_eq!(bisect(2, &0, |_| unreachable!()).unwrap(), (0, 1));
+
+        // Toolchains that can't be tested are skipped.
+        let outcomes = [Some(0), Some(0), None, None, Some(1)];
+        let pair = bisect(outcomes.len(), &0, |index| Ok(outcomes[index])).unwrap();
+        assert_eq!(pair, (1, 4));
+    }
+
+    #[test]
+    fn drifts_from_the_oldest_nightly_that_builds() {
+        let aliasing = Some(Detection::Ub(UbKind::Aliasing));
+        let clean = Some(Detection::Clean);
+        let outcomes = [None, None, clean, clean, None, aliasing, aliasing];
+        let mut runs = 0;
+        let drift = drift(outcomes.len(), |index| {
+            runs += 1;
+            Ok(outcomes[index])
+        })
+        .unwrap();
+        assert_eq!(drift.oldest, Some(2));
+        assert_eq!(drift.change, Some((3, 5)));
+        assert_eq!(runs, drift.outcomes.len());
+
+        let nightlies: Vec<Nightly> = (0..outcomes.len())
+            .map(|index| Nightly {
+                name: format!("nightly-{index}"),
+                date: format!("2024-01-0{}", index + 1),
+            })
+            .collect();
+        assert_eq!(
+            drift.change(&nightlies),
+            Some(Change {
+                before: "2024-01-04".to_owned(),
+                after: "2024-01-06".to_owned(),
+                from: Detection::Clean,
+                to: Detection::Ub(UbKind::Aliasing),
+            })
+        );
+
+        // Nothing to compare if the newest nightly can't build either.
+        let drift = super::drift(3, |index| Ok([clean, clean, None][index])).unwrap();
+        assert_eq!((drift.oldest, drift.change), (None, None));
+        assert_eq!(drift.cells(3), ["not run", "can't build"]);
+    }
+
+    #[test]
+    fn labels_the_oldest_and_newest_nightly() {
+        let drift = Drift {
+            outcomes: BTreeMap::from([(2, None)]),
+            ..Drift::default()
+        };
+        assert_eq!(drift.cells(3), ["not run", "can't build"]);
+
+        let drift = Drift {
+            outcomes: BTreeMap::from([(0, Some(Detection::Clean)), (1, None)]),
+            oldest: Some(0),
+            change: None,
+        };
+        assert_eq!(drift.cells(2), ["clean", "can't build"]);
+        assert_eq!(drift.cells(1), ["clean"]);
+    }
+
+    #[test]
+    fn lists_changes() {
+        let history = BTreeMap::from([
+            ("2022-06-01".to_owned(), Detection::Clean),
+            ("2024-01-10".to_owned(), Detection::Ub(UbKind::Aliasing)),
+            ("2024-01-11".to_owned(), Detection::Ub(UbKind::Aliasing)),
+        ]);
+        assert_eq!(
+            changes(&history),
+            [Change {
+                before: "2022-06-01".to_owned(),
+                after: "2024-01-10".to_owned(),
+                from: Detection::Clean,
+                to: Detection::Ub(UbKind::Aliasing),
+            }]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use zoo_bench::{booleans, capacity, chars, drain};
+
+const LENGTH: usize = 4096;
+
+fn bools(c: &mut Criterion) {
+    let bytes: Vec<u8> = (0..LENGTH).map(|i| u8::from(i % 3 == 0)).collect();
+    let mut group = c.benchmark_group("bool");
+    group.bench_function("unsound", |b| {
+        b.iter(|| booleans::unsound(black_box(&bytes)))
+    });
+    group.bench_function("sound", |b| b.iter(|| booleans::sound(black_box(&bytes))));
+    group.finish();
+}
+
+fn spare_capacity(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spare_capacity");
+    group.bench_function("raw_pointer", |b| {
+        b.iter(|| capacity::raw_pointer(black_box(LENGTH)))
+    });
+    group.bench_function("spare_capacity", |b| {
+        b.iter(|| capacity::spare_capacity(black_box(LENGTH)))
+    });
+    group.finish();
+}
+
+fn char_from_u32(c: &mut Criterion) {
+    // Mostly ASCII, with some of every other UTF-8 length.
+    let code_points: Vec<u32> = (0..LENGTH as u32)
+        .map(|i| match i % 8 {
+            0 => 0xe9,
+            1 => 0x4e2d,
+            2 => 0x1f980,
+            _ => 0x61 + i % 26,
+        })
+        .collect();
+    let mut group = c.benchmark_group("char_from_u32");
+    group.bench_function("unsound", |b| {
+        b.iter(|| chars::unsound(black_box(&code_points)))
+    });
+    group.bench_function("sound", |b| {
+        b.iter(|| chars::sound(black_box(&code_points)))
+    });
+    group.finish();
+}
+
+fn drains(c: &mut Criterion) {
+    let values = || (0..LENGTH as u64).collect::<Vec<u64>>();
+    let mut group = c.benchmark_group("drain");
+    group.bench_function("unsound", |b| {
+        b.iter_batched_ref(
+            values,
+            |v| {
+                drain::unsound(v).for_each(|value| {
+                    black_box(value);
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("sound", |b| {
+        b.iter_batched_ref(
+            values,
+            |v| {
+                drain::sound(v).for_each(|value| {
+                    black_box(value);
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("per_iteration", |b| {
+        b.iter_batched_ref(
+            values,
+            |v| {
+                drain::per_iteration(v).for_each(|value| {
+                    black_box(value);
+                })
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(patterns, bools, spare_capacity, char_from_u32, drains);
+criterion_main!(patterns);
@@ -0,0 +1,32 @@
+//! `invalid_bool`: turning bytes into `bool`s.
+
+use std::mem::transmute;
+
+/// Count the bytes that are `true`, by transmuting each one to a `bool`.
+/// This is only defined if every byte is 0 or 1.
+#[allow(clippy::transmute_int_to_bool)]
+pub fn unsound(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .filter(|&&byte| unsafe { transmute::<u8, bool>(byte) })
+        .count()
+}
+
+/// Count the bytes that aren't zero.
+pub fn sound(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&byte| byte != 0).count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strategies_agree() {
+        // The only bytes the transmute is defined for.
+        let bytes: Vec<u8> = (0..100).map(|i| u8::from(i % 3 == 0)).collect();
+        assert_eq!(unsound(&bytes), 34);
+        assert_eq!(sound(&bytes), 34);
+        assert_eq!(unsound(&[]), sound(&[]));
+    }
+}
@@ -0,0 +1,48 @@
+//! `uninitialized_memory`: filling a vector's spare capacity.
+//!
+//! There's no defined unsound version to time here: the shortcut
+//! `uninitialized_memory` takes, `get_unchecked_mut(i)` past the length, is
+//! undefined behavior for every `i` it's used for. So this times the two
+//! sound ways of doing it instead, the raw pointer people usually reach for
+//! and `spare_capacity_mut`.
+
+/// Make a vector of `0, 1, 2, ...` the way people usually do without
+/// `spare_capacity_mut`: writing each value through `as_mut_ptr().add(i)`,
+/// past the end of the vector, into its uninitialized capacity. That is
+/// defined, as long as nothing reads the capacity before `set_len`.
+pub fn raw_pointer(length: usize) -> Vec<u32> {
+    let mut v: Vec<u32> = Vec::with_capacity(length);
+    let base = v.as_mut_ptr();
+    for i in 0..length {
+        unsafe {
+            base.add(i).write(i as u32);
+        }
+    }
+    unsafe { v.set_len(length) };
+    v
+}
+
+/// Write the same values through `spare_capacity_mut`, which hands out the
+/// capacity as `MaybeUninit`s.
+pub fn spare_capacity(length: usize) -> Vec<u32> {
+    let mut v = Vec::with_capacity(length);
+    for (i, slot) in v.spare_capacity_mut()[..length].iter_mut().enumerate() {
+        slot.write(i as u32);
+    }
+    unsafe { v.set_len(length) };
+    v
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strategies_agree() {
+        for length in [0, 1, 7, 64] {
+            let expected: Vec<u32> = (0..length as u32).collect();
+            assert_eq!(raw_pointer(length), expected);
+            assert_eq!(spare_capacity(length), expected);
+        }
+    }
+}
@@ -0,0 +1,37 @@
+//! `invalid_char`: turning code points into `char`s.
+
+/// The length of the code points encoded as UTF-8, converting each one with
+/// `char::from_u32_unchecked`. This is only defined if none of them is a
+/// surrogate or past `char::MAX`.
+pub fn unsound(code_points: &[u32]) -> usize {
+    code_points
+        .iter()
+        .map(|&c| unsafe { char::from_u32_unchecked(c) }.len_utf8())
+        .sum()
+}
+
+/// The same length, converting with `char::from_u32`, and counting invalid
+/// code points as U+FFFD.
+pub fn sound(code_points: &[u32]) -> usize {
+    code_points
+        .iter()
+        .map(|&c| {
+            char::from_u32(c)
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+                .len_utf8()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strategies_agree() {
+        // Valid code points of every UTF-8 length, up to `char::MAX`.
+        let code_points = [0x61, 0xe9, 0x4e2d, 0xd7ff, 0xe000, 0x1f980, 0x10ffff];
+        assert_eq!(unsound(&code_points), 1 + 2 + 3 + 3 + 3 + 4 + 4);
+        assert_eq!(sound(&code_points), unsound(&code_points));
+    }
+}
@@ -0,0 +1,117 @@
+//! `buggy_drain`: ways for a `Drain` to keep its vector valid.
+
+use std::ptr;
+
+/// `buggy_drain`'s `Drain`, over a `Vec`: it reads the values out of the
+/// vector in place, and moves the ones it didn't take to the front when it's
+/// dropped.
+pub struct Drain<'a, T> {
+    vec: &'a mut Vec<T>,
+    index: usize,
+    length: usize,
+}
+
+impl<T> Iterator for Drain<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.index >= self.length {
+            None
+        } else {
+            let value = unsafe { ptr::read(self.vec.as_ptr().add(self.index)) };
+            self.index += 1;
+            Some(value)
+        }
+    }
+}
+
+impl<T> Drop for Drain<'_, T> {
+    fn drop(&mut self) {
+        let count = self.length - self.index;
+        unsafe {
+            let src = self.vec.as_ptr().add(self.index);
+            ptr::copy(src, self.vec.as_mut_ptr(), count);
+            self.vec.set_len(count);
+        }
+    }
+}
+
+/// The original: the vector keeps its length until the `Drain` is dropped,
+/// so leaking the `Drain` leaves it holding values that were taken.
+pub fn unsound<T>(vec: &mut Vec<T>) -> Drain<'_, T> {
+    let length = vec.len();
+    Drain {
+        vec,
+        index: 0,
+        length,
+    }
+}
+
+/// The fix: the vector claims to be empty while the `Drain` exists.
+pub fn sound<T>(vec: &mut Vec<T>) -> Drain<'_, T> {
+    let length = vec.len();
+    unsafe { vec.set_len(0) };
+    Drain {
+        vec,
+        index: 0,
+        length,
+    }
+}
+
+/// The other way to keep the vector valid: take each value out of it as the
+/// iterator goes, updating the length every time, so that a leaked iterator
+/// leaves the vector holding every value it didn't take. Only the end of a
+/// `Vec` can be cut off without moving the rest, so the vector is reversed
+/// up front, each `next()` pops a value, and dropping the iterator puts the
+/// rest back in order.
+pub struct PerIteration<'a, T> {
+    vec: &'a mut Vec<T>,
+}
+
+impl<T> Iterator for PerIteration<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.vec.pop()
+    }
+}
+
+impl<T> Drop for PerIteration<'_, T> {
+    fn drop(&mut self) {
+        self.vec.reverse();
+    }
+}
+
+pub fn per_iteration<T>(vec: &mut Vec<T>) -> PerIteration<'_, T> {
+    vec.reverse();
+    PerIteration { vec }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Strategy = fn(&mut Vec<u64>) -> Drain<'_, u64>;
+
+    #[test]
+    fn strategies_agree() {
+        for taken in 0..=4 {
+            let strategies: [Strategy; 2] = [unsound, sound];
+            let mut results = Vec::new();
+            for strategy in strategies {
+                let mut v: Vec<u64> = (0..4).collect();
+                let values: Vec<u64> = strategy(&mut v).take(taken).collect();
+                results.push((values, v));
+            }
+            let mut v: Vec<u64> = (0..4).collect();
+            let values: Vec<u64> = per_iteration(&mut v).take(taken).collect();
+            results.push((values, v));
+
+            let expected = ((0..taken as u64).collect(), (taken as u64..4).collect());
+            assert!(
+                results.iter().all(|result| *result == expected),
+                "{results:?}"
+            );
+        }
+    }
+}
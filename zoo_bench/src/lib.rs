@@ -0,0 +1,13 @@
+//! Unsound shortcuts from the examples, next to their sound replacements, so
+//! that `cargo bench -p zoo_bench` can measure what doing it right costs.
+//!
+//! Each module has an `unsound` and a `sound` function that do the same work.
+//! The benchmarks only give the unsound ones inputs on which they happen to
+//! be defined; the point is to time them, not to trigger the bugs.
+//! `capacity` is the exception: its shortcut is never defined, so it
+//! compares two sound ways of doing the same thing.
+
+pub mod booleans;
+pub mod capacity;
+pub mod chars;
+pub mod drain;
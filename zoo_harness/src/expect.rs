@@ -0,0 +1,229 @@
+//! Declaring what Miri is expected to report for each test.
+//!
+//! Most tests in the zoo "succeed" by making Miri stop with an undefined
+//! behavior error. That's only a success if it's the *right* error, so each
+//! test declares the kind of UB it triggers and a fragment of the message
+//! Miri prints for it.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A broad category of undefined behavior, derived from Miri's error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum UbKind {
+    /// A value was produced that is invalid for its type: a `bool` that isn't
+    /// 0 or 1, a null or dangling reference, an unaligned `Box`, ...
+    InvalidValue,
+    /// Uninitialized memory was read.
+    Uninit,
+    /// An aliasing rule (Stacked Borrows or Tree Borrows) was violated.
+    Aliasing,
+    /// Memory was accessed through a pointer that doesn't point to it.
+    Dangling,
+    /// Memory was accessed through a pointer that isn't properly aligned.
+    Misaligned,
+    /// Memory was freed that wasn't allocated, or was freed twice.
+    InvalidFree,
+    /// Two threads accessed the same memory without synchronization.
+    DataRace,
+    /// Code that promised to be unreachable was reached, often via a library
+    /// precondition compiled into `assume` or `unreachable_unchecked`.
+    Unreachable,
+    /// Anything not covered above.
+    Other,
+}
+
+impl UbKind {
+    pub const ALL: [UbKind; 9] = [
+        UbKind::InvalidValue,
+        UbKind::Uninit,
+        UbKind::Aliasing,
+        UbKind::Dangling,
+        UbKind::Misaligned,
+        UbKind::InvalidFree,
+        UbKind::DataRace,
+        UbKind::Unreachable,
+        UbKind::Other,
+    ];
+
+    /// The name used in `#[expect_ub(kind = "...")]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            UbKind::InvalidValue => "invalid-value",
+            UbKind::Uninit => "uninit",
+            UbKind::Aliasing => "aliasing",
+            UbKind::Dangling => "dangling",
+            UbKind::Misaligned => "misaligned",
+            UbKind::InvalidFree => "invalid-free",
+            UbKind::DataRace => "data-race",
+            UbKind::Unreachable => "unreachable",
+            UbKind::Other => "other",
+        }
+    }
+
+    /// Work out which kind of UB a Miri error message describes.
+    ///
+    /// `message` is the text following `error: Undefined Behavior: `.
+    pub fn classify(message: &str) -> UbKind {
+        // Order matters: "constructing invalid value: encountered a dangling
+        // box" is an invalid value, not a dangling access.
+        const PATTERNS: &[(&str, UbKind)] = &[
+            ("constructing invalid value", UbKind::InvalidValue),
+            ("Data race detected", UbKind::DataRace),
+            ("borrow stack", UbKind::Aliasing),
+            ("but that tag only grants", UbKind::Aliasing),
+            ("is forbidden", UbKind::Aliasing),
+            ("strongly protected", UbKind::Aliasing),
+            ("trying to retag", UbKind::Aliasing),
+            ("uninitialized", UbKind::Uninit),
+            ("alignment", UbKind::Misaligned),
+            ("deallocat", UbKind::InvalidFree),
+            ("dangling", UbKind::Dangling),
+            ("out-of-bounds", UbKind::Dangling),
+            ("has been freed", UbKind::Dangling),
+            ("null pointer", UbKind::Dangling),
+            ("unreachable", UbKind::Unreachable),
+            ("`assume` called with `false`", UbKind::Unreachable),
+        ];
+
+        PATTERNS
+            .iter()
+            .find(|(pattern, _)| message.contains(pattern))
+            .map_or(UbKind::Other, |&(_, kind)| kind)
+    }
+}
+
+impl fmt::Display for UbKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The error returned when parsing an unknown [`UbKind`] name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownKind(pub String);
+
+impl fmt::Display for UnknownKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<_> = UbKind::ALL.iter().map(|kind| kind.name()).collect();
+        write!(
+            f,
+            "unknown UB kind `{}` (expected one of: {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownKind {}
+
+impl FromStr for UbKind {
+    type Err = UnknownKind;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        UbKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| UnknownKind(s.to_owned()))
+    }
+}
+
+/// What a test should do when it runs under Miri.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expectation {
+    /// Miri stops with undefined behavior of this kind, and its message
+    /// contains `message`.
+    Ub { kind: UbKind, message: String },
+    /// The test runs to completion without Miri reporting anything.
+    Clean,
+}
+
+impl Expectation {
+    /// Check the expectation against the UB message Miri reported, if any.
+    pub fn is_met_by(&self, ub_message: Option<&str>) -> bool {
+        match (self, ub_message) {
+            (Expectation::Ub { kind, message }, Some(actual)) => {
+                UbKind::classify(actual) == *kind && actual.contains(message.as_str())
+            }
+            (Expectation::Clean, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expectation::Ub { kind, message } => write!(f, "{kind} UB: {message}"),
+            Expectation::Clean => f.write_str("clean"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for kind in UbKind::ALL {
+            assert_eq!(kind.name().parse(), Ok(kind));
+        }
+        assert!("invalid_value".parse::<UbKind>().is_err());
+    }
+
+    #[test]
+    fn classifies_miri_messages() {
+        let cases = [
+            (
+                "constructing invalid value of type bool: encountered 0x02, but expected a boolean",
+                UbKind::InvalidValue,
+            ),
+            (
+                "constructing invalid value of type std::boxed::Box<u32>: encountered a dangling box (use-after-free)",
+                UbKind::InvalidValue,
+            ),
+            (
+                "attempting a read access using <132418> at alloc41750[0x0], but that tag does not exist in the borrow stack for this location",
+                UbKind::Aliasing,
+            ),
+            (
+                "attempting a write access using <134498> at alloc41701[0x0], but that tag only grants SharedReadOnly permission for this location",
+                UbKind::Aliasing,
+            ),
+            (
+                "not granting access to tag <134277> because that would remove [SharedReadOnly for <132431>] which is strongly protected",
+                UbKind::Aliasing,
+            ),
+            (
+                "reading memory at alloc41714[0x0..0x4], but memory is uninitialized at [0x0..0x4], and this operation requires initialized memory",
+                UbKind::Uninit,
+            ),
+            ("memory access failed: alloc1234 has been freed, so this pointer is dangling", UbKind::Dangling),
+            ("entering unreachable code", UbKind::Unreachable),
+            ("`assume` called with `false`", UbKind::Unreachable),
+            ("something Miri invented last week", UbKind::Other),
+        ];
+        for (message, kind) in cases {
+            assert_eq!(UbKind::classify(message), kind, "{message}");
+        }
+    }
+
+    #[test]
+    fn expectation_checks_kind_and_message() {
+        let expected = Expectation::Ub {
+            kind: UbKind::InvalidValue,
+            message: "encountered 0x02, but expected a boolean".to_owned(),
+        };
+        assert!(expected.is_met_by(Some(
+            "constructing invalid value of type bool: encountered 0x02, but expected a boolean"
+        )));
+        assert!(!expected.is_met_by(Some(
+            "constructing invalid value of type bool: encountered 0x03, but expected a boolean"
+        )));
+        assert!(!expected.is_met_by(None));
+
+        assert!(Expectation::Clean.is_met_by(None));
+        assert!(!Expectation::Clean.is_met_by(Some("entering unreachable code")));
+    }
+}
@@ -0,0 +1,22 @@
+//! Heap-allocated test values.
+
+/// Returns `N` boxed bytes holding the values `0, 1, 2, ...`.
+///
+/// Values that own a heap allocation make memory errors obvious: a
+/// use-after-free or double free of a `Box` is something Miri (and the
+/// system allocator) will notice, where a plain integer might silently
+/// appear to work.
+pub fn boxed_bytes<const N: usize>() -> [Box<u8>; N] {
+    std::array::from_fn(|i| Box::new(i as u8))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counts_up_from_zero() {
+        let values = boxed_bytes::<3>();
+        assert_eq!(values.map(|b| *b), [0, 1, 2]);
+    }
+}
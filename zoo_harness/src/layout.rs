@@ -0,0 +1,162 @@
+//! Printing the memory layout of a type, including its padding bytes.
+
+use std::fmt;
+use std::ops::Range;
+
+/// The size, alignment and field placement of a type.
+///
+/// This is usually built with the [`print_layout!`](crate::print_layout)
+/// macro, which fills in the field offsets automatically.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeLayout {
+    pub name: &'static str,
+    pub size: usize,
+    pub align: usize,
+    /// `(name, byte range)` for each field, sorted by offset.
+    pub fields: Vec<(&'static str, Range<usize>)>,
+}
+
+impl TypeLayout {
+    /// Describe `T`, given the name, offset and size of each of its fields.
+    pub fn new<T>(name: &'static str, fields: &[(&'static str, usize, usize)]) -> Self {
+        let mut fields: Vec<_> = fields
+            .iter()
+            .map(|&(name, offset, size)| (name, offset..offset + size))
+            .collect();
+        fields.sort_by_key(|(_, range)| range.start);
+
+        TypeLayout {
+            name,
+            size: std::mem::size_of::<T>(),
+            align: std::mem::align_of::<T>(),
+            fields,
+        }
+    }
+
+    /// The byte ranges that don't belong to any field.
+    ///
+    /// Reading these bytes is reading uninitialized memory.
+    pub fn padding(&self) -> Vec<Range<usize>> {
+        let mut padding = Vec::new();
+        let mut cursor = 0;
+        for (_, range) in &self.fields {
+            if range.start > cursor {
+                padding.push(cursor..range.start);
+            }
+            cursor = cursor.max(range.end);
+        }
+        if cursor < self.size {
+            padding.push(cursor..self.size);
+        }
+        padding
+    }
+}
+
+impl fmt::Display for TypeLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: size {}, align {}", self.name, self.size, self.align)?;
+
+        let mut rows: Vec<_> = self
+            .fields
+            .iter()
+            .map(|(name, range)| (range.clone(), *name))
+            .chain(self.padding().into_iter().map(|range| (range, "(padding)")))
+            .collect();
+        rows.sort_by_key(|(range, _)| range.start);
+
+        for (range, name) in rows {
+            let bytes = format!("{}..{}", range.start, range.end);
+            writeln!(f, "  {bytes:<8}{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the size of the field selected by `field`.
+///
+/// This exists so that [`print_layout!`](crate::print_layout) can learn a
+/// field's size without having a value of the containing type.
+pub fn field_size<T, U>(_field: fn(&T) -> &U) -> usize {
+    std::mem::size_of::<U>()
+}
+
+/// Print a struct's size, alignment, field offsets and padding to stderr.
+///
+/// ```
+/// #[repr(C)]
+/// struct StructWithPadding {
+///     x: u8,
+///     y: u32,
+/// }
+///
+/// zoo_harness::print_layout!(StructWithPadding { x, y });
+/// ```
+///
+/// prints
+///
+/// ```txt
+/// StructWithPadding: size 8, align 4
+///   0..1    x
+///   1..4    (padding)
+///   4..8    y
+/// ```
+#[macro_export]
+macro_rules! print_layout {
+    ($ty:ty { $($field:ident),+ $(,)? }) => {
+        ::std::eprint!(
+            "{}",
+            $crate::layout::TypeLayout::new::<$ty>(::std::stringify!($ty), &[$((
+                ::std::stringify!($field),
+                ::std::mem::offset_of!($ty, $field),
+                $crate::layout::field_size(|value: &$ty| &value.$field),
+            )),+])
+        )
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[allow(dead_code)]
+    #[repr(C)]
+    struct StructWithPadding {
+        x: u8,
+        y: u32,
+        z: u16,
+    }
+
+    fn layout() -> TypeLayout {
+        TypeLayout::new::<StructWithPadding>(
+            "StructWithPadding",
+            &[
+                ("x", std::mem::offset_of!(StructWithPadding, x), 1),
+                ("y", std::mem::offset_of!(StructWithPadding, y), 4),
+                ("z", std::mem::offset_of!(StructWithPadding, z), 2),
+            ],
+        )
+    }
+
+    #[test]
+    fn finds_interior_and_trailing_padding() {
+        assert_eq!(layout().padding(), vec![1..4, 10..12]);
+    }
+
+    #[test]
+    fn display_interleaves_fields_and_padding() {
+        let expected = "\
+StructWithPadding: size 12, align 4
+  0..1    x
+  1..4    (padding)
+  4..8    y
+  8..10   z
+  10..12  (padding)
+";
+        assert_eq!(layout().to_string(), expected);
+    }
+
+    #[test]
+    fn field_size_measures_the_field_type() {
+        assert_eq!(field_size(|s: &StructWithPadding| &s.z), 2);
+    }
+}
@@ -0,0 +1,14 @@
+//! Shared helpers for the Unsound Zoo examples.
+//!
+//! Every crate under `unsound_examples/` depends on this one. The helpers
+//! here are deliberately small: the examples are meant to be read, so
+//! anything that isn't part of the lesson (building test values, printing
+//! memory layouts, describing what Miri should say) lives here instead.
+
+pub mod expect;
+pub mod heap;
+pub mod layout;
+
+pub use expect::{Expectation, UbKind};
+pub use heap::boxed_bytes;
+pub use zoo_macros::{expect_clean, expect_ub};
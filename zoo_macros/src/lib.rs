@@ -0,0 +1,158 @@
+//! Attribute macros for declaring what Miri should report for a test.
+//!
+//! These are re-exported from `zoo_harness`, which is where the examples
+//! use them from. The runner reads the attributes back out of the source and
+//! compares them against Miri's actual diagnostics.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span};
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, ItemFn, LitStr};
+
+/// Declare that a test triggers undefined behavior, and what Miri says
+/// about it.
+///
+/// ```ignore
+/// #[zoo_harness::expect_ub(
+///     kind = "invalid-value",
+///     message = "encountered 0x02, but expected a boolean"
+/// )]
+/// #[test]
+/// fn transmute_to_bool() {
+///     let _y: bool = unsafe { std::mem::transmute(2u8) };
+/// }
+/// ```
+///
+/// `kind` is one of the `zoo_harness::UbKind` names, and `message` is a
+/// fragment of Miri's "Undefined Behavior:" line. A test that stops with
+/// any other error is reported by the runner as a regression.
+///
+/// Running undefined behavior outside of Miri proves nothing, so the test
+/// is also marked `#[ignore]` unless it is being run under Miri, or the
+/// example's `sound` feature swaps in the corrected code. For that to work,
+/// this attribute has to be placed above `#[test]`.
+///
+/// The declared UB is what the test does without the `sound` feature; with
+/// it, the test is expected to run clean.
+#[proc_macro_attribute]
+pub fn expect_ub(args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut kind: Option<LitStr> = None;
+    let mut message: Option<LitStr> = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("kind") {
+            kind = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("message") {
+            message = Some(meta.value()?.parse()?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported expect_ub argument; expected `kind` or `message`"))
+        }
+    });
+    parse_macro_input!(args with parser);
+    let function = parse_macro_input!(item as ItemFn);
+    if let Err(error) = check_placement(&function, "expect_ub") {
+        return error.to_compile_error().into();
+    }
+
+    let Some(kind) = kind else {
+        return missing_argument("kind");
+    };
+    let Some(message) = message else {
+        return missing_argument("message");
+    };
+    if message.value().trim().is_empty() {
+        return syn::Error::new(message.span(), "`message` may not be empty")
+            .to_compile_error()
+            .into();
+    }
+    let variant = match kind_variant(&kind) {
+        Ok(variant) => variant,
+        Err(error) => return error.to_compile_error().into(),
+    };
+
+    // Naming the variant turns a misspelled kind into a compile error.
+    let check_kind = quote_spanned! {kind.span()=>
+        const _: ::zoo_harness::UbKind = ::zoo_harness::UbKind::#variant;
+    };
+    quote! {
+        #check_kind
+        #[cfg_attr(
+            all(not(miri), not(feature = "sound")),
+            ignore = "undefined behavior; run under Miri"
+        )]
+        #function
+    }
+    .into()
+}
+
+/// Declare that a test should run to completion under Miri, without any
+/// undefined behavior being reported.
+///
+/// This is for the tests that show the *correct* way to do something, and
+/// for tests of the "sound" halves of an example.
+#[proc_macro_attribute]
+pub fn expect_clean(args: TokenStream, item: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        return syn::Error::new(Span::call_site(), "expect_clean takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let function = parse_macro_input!(item as ItemFn);
+    if let Err(error) = check_placement(&function, "expect_clean") {
+        return error.to_compile_error().into();
+    }
+    quote!(#function).into()
+}
+
+/// The expectation attributes must come before `#[test]`.
+///
+/// Attributes expand top to bottom, and `#[test]` decides whether a test is
+/// ignored when it expands; an `ignore` added by a later attribute would be
+/// silently dropped. If `#[test]` has already expanded, it's no longer in
+/// the function's attribute list.
+fn check_placement(function: &ItemFn, macro_name: &str) -> syn::Result<()> {
+    if function
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("test"))
+    {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            function.sig.ident.span(),
+            format!("#[{macro_name}] must be placed above #[test]"),
+        ))
+    }
+}
+
+fn missing_argument(name: &str) -> TokenStream {
+    syn::Error::new(
+        Span::call_site(),
+        format!("expect_ub requires a `{name} = \"...\"` argument"),
+    )
+    .to_compile_error()
+    .into()
+}
+
+/// Convert a kebab-case kind name like `invalid-value` into the matching
+/// `UbKind` variant name, `InvalidValue`.
+fn kind_variant(kind: &LitStr) -> syn::Result<Ident> {
+    let name = kind.value();
+    let well_formed = !name.is_empty()
+        && name
+            .split('-')
+            .all(|word| !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase()));
+    if !well_formed {
+        return Err(syn::Error::new(
+            kind.span(),
+            format!("UB kind `{name}` should be written in kebab-case, like `invalid-value`"),
+        ));
+    }
+
+    let variant: String = name
+        .split('-')
+        .map(|word| word[..1].to_ascii_uppercase() + &word[1..])
+        .collect();
+    Ok(Ident::new(&variant, kind.span()))
+}